@@ -0,0 +1,159 @@
+//! A client for the selector's [serial protocol](autons_controller::serial).
+
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, BufReader, Read, Write},
+};
+
+pub use autons_controller::{
+    serial::{Request, Response, RouteRef},
+    Action,
+};
+
+/// Talks to a robot over a serial connection, or anything else that reads and writes bytes.
+///
+/// Lines which aren't part of the protocol, such as the robot's own prints, are skipped. If the
+/// stream has a read timeout, a request the robot doesn't answer in time fails with
+/// [`io::ErrorKind::TimedOut`], while [`next_change`](Self::next_change) keeps waiting.
+pub struct Client<S: Read + Write> {
+    stream: BufReader<S>,
+    /// The line being read, kept across timeouts so a slow line isn't cut in half.
+    line: String,
+    /// Changes which arrived while waiting for another response.
+    changes: VecDeque<Vec<RouteRef>>,
+}
+
+impl<S: Read + Write> Client<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+            line: String::new(),
+            changes: VecDeque::new(),
+        }
+    }
+
+    /// Lists every route on the robot.
+    pub fn list(&mut self) -> io::Result<Vec<RouteRef>> {
+        self.send(&Request::List)?;
+        let mut routes = Vec::new();
+        loop {
+            match self.response()? {
+                Response::Route(route) => routes.push(route),
+                Response::Ok => return Ok(routes),
+                other => return Err(unexpected(other)),
+            }
+        }
+    }
+
+    /// Returns the routes that will run, which is empty if nothing is selected.
+    pub fn get(&mut self) -> io::Result<Vec<RouteRef>> {
+        self.send(&Request::Get)?;
+        match self.response()? {
+            Response::Selected(routes) => Ok(routes),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Selects a route.
+    pub fn select(&mut self, route: &RouteRef) -> io::Result<()> {
+        self.send(&Request::Select(route.clone()))?;
+        self.expect_ok()
+    }
+
+    /// Navigates the controller menu as if the driver had pressed a button.
+    pub fn press(&mut self, action: Action) -> io::Result<()> {
+        self.send(&Request::Press(action))?;
+        self.expect_ok()
+    }
+
+    /// Asks the robot to report every change to the selection, which are read with
+    /// [`next_change`](Self::next_change).
+    pub fn subscribe(&mut self) -> io::Result<()> {
+        self.send(&Request::Subscribe)?;
+        self.expect_ok()
+    }
+
+    /// Stops reporting changes to the selection.
+    pub fn unsubscribe(&mut self) -> io::Result<()> {
+        self.send(&Request::Unsubscribe)?;
+        self.expect_ok()?;
+        self.changes.clear();
+        Ok(())
+    }
+
+    /// Waits for the selection to change while subscribed, returning the new selection.
+    pub fn next_change(&mut self) -> io::Result<Vec<RouteRef>> {
+        if let Some(change) = self.changes.pop_front() {
+            return Ok(change);
+        }
+        loop {
+            match self.read_response() {
+                Ok(Response::Changed(routes)) => return Ok(routes),
+                Err(error) if error.kind() != io::ErrorKind::TimedOut => return Err(error),
+                _ => {}
+            }
+        }
+    }
+
+    fn send(&mut self, request: &Request) -> io::Result<()> {
+        let stream = self.stream.get_mut();
+        stream.write_all(format!("{request}\n").as_bytes())?;
+        stream.flush()
+    }
+
+    fn expect_ok(&mut self) -> io::Result<()> {
+        match self.response()? {
+            Response::Ok => Ok(()),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    /// Reads the next response to a request, setting aside any changes that arrive first.
+    fn response(&mut self) -> io::Result<Response> {
+        loop {
+            match self.read_response()? {
+                Response::Changed(routes) => self.changes.push_back(routes),
+                response => return Ok(response),
+            }
+        }
+    }
+
+    fn read_response(&mut self) -> io::Result<Response> {
+        loop {
+            match self.stream.read_line(&mut self.line) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(_) if !self.line.ends_with('\n') => continue,
+                Ok(_) => {}
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+                    ) =>
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "the robot didn't answer in time",
+                    ));
+                }
+                Err(error) => return Err(error),
+            }
+            let response = Response::parse(&self.line);
+            self.line.clear();
+            if let Some(response) = response {
+                return Ok(response);
+            }
+        }
+    }
+}
+
+/// Turns an error from the robot, or a response that doesn't fit the request, into an error.
+fn unexpected(response: Response) -> io::Error {
+    match response {
+        Response::Err(message) => io::Error::other(message),
+        other => io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unexpected response `{other}`"),
+        ),
+    }
+}
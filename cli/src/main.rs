@@ -0,0 +1,118 @@
+//! Lists and selects routes on a robot over USB serial.
+//!
+//! ```text
+//! autons-serial --port /dev/ttyACM1 list
+//! autons-serial --port /dev/ttyACM1 select "Red/Left"
+//! ```
+//!
+//! The port is a path like `/dev/ttyACM1` on Linux, `/dev/cu.usbmodem1103` on macOS or a name
+//! like `COM3` on Windows. It can also be set with the `AUTONS_PORT` environment variable.
+//!
+//! The robot's selector has to be built with `.serial(serial::Stdio)`.
+
+use std::{env, io, process::ExitCode, time::Duration};
+
+use autons_controller_cli::{Action, Client, Request, RouteRef};
+use serialport::SerialPort;
+
+const USAGE: &str = "\
+usage: autons-serial [--port <path>] <command>
+
+commands:
+    list                    list every route
+    get                     show the routes that will run
+    select <category/name>  select a route
+    watch                   show the selection whenever it changes
+    press <action>          navigate the controller menu, where <action> is next,
+                            previous, confirm, back, next-group, previous-group,
+                            exit, reopen or jump <index>";
+
+enum Operation {
+    List,
+    Get,
+    Select(RouteRef),
+    Watch,
+    Press(Action),
+}
+
+fn main() -> ExitCode {
+    match run(env::args().skip(1).collect()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("autons-serial: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(mut args: Vec<String>) -> io::Result<()> {
+    let mut port = env::var("AUTONS_PORT").ok();
+    if let Some(i) = args.iter().position(|arg| arg == "--port") {
+        args.remove(i);
+        if i >= args.len() {
+            return Err(usage());
+        }
+        port = Some(args.remove(i));
+    }
+    let port = port.ok_or_else(|| io::Error::other("no port given; use --port or AUTONS_PORT"))?;
+
+    let operation = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["list"] => Operation::List,
+        ["get"] => Operation::Get,
+        ["select", route] => Operation::Select(RouteRef::parse(route).ok_or_else(usage)?),
+        ["watch"] => Operation::Watch,
+        ["press", ..] => match Request::parse(&args.join(" ")) {
+            Ok(Request::Press(action)) => Operation::Press(action),
+            _ => return Err(usage()),
+        },
+        _ => return Err(usage()),
+    };
+
+    let mut client = Client::new(open(&port)?);
+    match operation {
+        Operation::List => {
+            for route in client.list()? {
+                println!("{route}");
+            }
+        }
+        Operation::Get => print_selection(&client.get()?),
+        Operation::Select(route) => {
+            client.select(&route)?;
+            println!("Selected {route}");
+        }
+        Operation::Press(action) => client.press(action)?,
+        Operation::Watch => {
+            client.subscribe()?;
+            print_selection(&client.get()?);
+            loop {
+                print_selection(&client.next_change()?);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_selection(routes: &[RouteRef]) {
+    if routes.is_empty() {
+        println!("Nothing selected");
+    }
+    for route in routes {
+        println!("{route}");
+    }
+}
+
+fn usage() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, USAGE)
+}
+
+/// How long to wait for the robot to answer a request.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Opens a serial port in raw mode, so nothing sent is echoed or rewritten.
+fn open(port: &str) -> io::Result<Box<dyn SerialPort>> {
+    // The brain's USB port ignores the baud rate, but one has to be given
+    serialport::new(port, 115_200)
+        .timeout(TIMEOUT)
+        .open()
+        .map_err(|error| io::Error::other(format!("couldn't open {port}: {error}")))
+}
@@ -0,0 +1,320 @@
+//! Talks to a selector running on the emulator over a socket, as if it were a robot on USB.
+//!
+//! Run with `cargo host-test`.
+
+extern crate alloc;
+
+use std::{
+    cell::RefCell,
+    io::{self, BufRead, BufReader, Read, Write},
+    os::unix::net::UnixStream,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use autons_controller::{
+    emulator::{self, Controller},
+    prelude::*,
+    serial::{Request, Response, SerialLink},
+};
+use autons_controller_cli::{Action, Client, RouteRef};
+
+struct Robot;
+
+impl Robot {
+    async fn left(&mut self) {}
+    async fn right(&mut self) {}
+    async fn skills(&mut self) {}
+}
+
+/// A computed name with every character that means something in the protocol.
+const TRICKY_NAME: &str = "Lap 1/2; fast \\ safe\nline";
+
+/// The robot's end of the socket.
+struct SocketLink {
+    stream: UnixStream,
+    /// Whether writes are refused every other time and otherwise cut short, like a busy stdout.
+    choked: bool,
+    refuse: bool,
+}
+
+impl SerialLink for SocketLink {
+    fn read(&mut self, buf: &mut [u8]) -> usize {
+        self.stream.read(buf).unwrap_or(0)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> usize {
+        if !self.choked {
+            return self.stream.write(bytes).unwrap_or(0);
+        }
+        self.refuse = !self.refuse;
+        if self.refuse {
+            return 0;
+        }
+        self.stream.write(&bytes[..bytes.len().min(5)]).unwrap_or(0)
+    }
+}
+
+/// A selector answering on a socket from its own thread, stopped when dropped.
+struct RobotThread {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl RobotThread {
+    /// Starts the robot, returning it and the computer's end of the socket.
+    fn start() -> (Self, UnixStream) {
+        Self::start_with(false)
+    }
+
+    /// Starts a robot whose writes are often refused or cut short.
+    fn start_choked() -> (Self, UnixStream) {
+        Self::start_with(true)
+    }
+
+    fn start_with(choked: bool) -> (Self, UnixStream) {
+        let (robot, computer) = UnixStream::pair().unwrap();
+        robot.set_nonblocking(true).unwrap();
+        computer
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = thread::spawn({
+            let stop = stop.clone();
+            move || {
+                let _selector = ControllerSelect::builder(
+                    Rc::new(RefCell::new(Controller::new())),
+                    Rc::new(RefCell::new(true)),
+                    [
+                        route!("Match", "Left", Robot::left),
+                        route!("Match", "Right", Robot::right),
+                        route!("Skills", "Skills", Robot::skills),
+                        route!("Skills", TRICKY_NAME, Robot::skills),
+                    ],
+                )
+                .serial(SocketLink {
+                    stream: robot,
+                    choked,
+                    refuse: false,
+                })
+                .build();
+                while !stop.load(Ordering::Relaxed) {
+                    emulator::advance(Duration::from_millis(20));
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+        });
+        let robot = Self {
+            stop,
+            thread: Some(thread),
+        };
+        (robot, computer)
+    }
+}
+
+impl Drop for RobotThread {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.join().unwrap();
+        }
+    }
+}
+
+#[test]
+fn lists_routes() {
+    let (_robot, stream) = RobotThread::start();
+    let mut client = Client::new(stream);
+    assert_eq!(
+        client.list().unwrap(),
+        [
+            RouteRef::new("Match", "Left"),
+            RouteRef::new("Match", "Right"),
+            RouteRef::new("Skills", "Skills"),
+            RouteRef::new("Skills", TRICKY_NAME),
+        ]
+    );
+}
+
+#[test]
+fn selects_a_route() {
+    let (_robot, stream) = RobotThread::start();
+    let mut client = Client::new(stream);
+    assert_eq!(client.get().unwrap(), []);
+    client.select(&RouteRef::new("Match", "Right")).unwrap();
+    assert_eq!(client.get().unwrap(), [RouteRef::new("Match", "Right")]);
+}
+
+#[test]
+fn escapes_names_with_separators() {
+    let (_robot, stream) = RobotThread::start();
+    let mut client = Client::new(stream);
+    client.subscribe().unwrap();
+    client
+        .select(&RouteRef::new("Skills", TRICKY_NAME))
+        .unwrap();
+    assert_eq!(
+        client.next_change().unwrap(),
+        [RouteRef::new("Skills", TRICKY_NAME)]
+    );
+    assert_eq!(
+        client.get().unwrap(),
+        [RouteRef::new("Skills", TRICKY_NAME)]
+    );
+}
+
+#[test]
+fn rejects_unknown_routes() {
+    let (_robot, stream) = RobotThread::start();
+    let mut client = Client::new(stream);
+    let error = client
+        .select(&RouteRef::new("Match", "Middle"))
+        .unwrap_err();
+    assert_eq!(error.to_string(), "no route named `Match/Middle`");
+    assert_eq!(client.get().unwrap(), []);
+}
+
+#[test]
+fn reports_changes_while_subscribed() {
+    let (_robot, stream) = RobotThread::start();
+    let mut client = Client::new(stream);
+    client.subscribe().unwrap();
+    client.select(&RouteRef::new("Skills", "Skills")).unwrap();
+    assert_eq!(
+        client.next_change().unwrap(),
+        [RouteRef::new("Skills", "Skills")]
+    );
+    client.select(&RouteRef::new("Match", "Left")).unwrap();
+    assert_eq!(
+        client.next_change().unwrap(),
+        [RouteRef::new("Match", "Left")]
+    );
+    client.unsubscribe().unwrap();
+}
+
+#[test]
+fn navigates_the_menu() {
+    let (_robot, stream) = RobotThread::start();
+    let mut client = Client::new(stream);
+    client.subscribe().unwrap();
+    // Pick the "Skills" category, its only route, then move from "Cancel" to "Confirm"
+    for action in [
+        Action::Jump(1),
+        Action::Confirm,
+        Action::Confirm,
+        Action::Next,
+        Action::Confirm,
+    ] {
+        client.press(action).unwrap();
+    }
+    assert_eq!(
+        client.next_change().unwrap(),
+        [RouteRef::new("Skills", "Skills")]
+    );
+}
+
+#[test]
+fn sends_the_rest_of_a_response_later() {
+    let (_robot, stream) = RobotThread::start_choked();
+    let mut client = Client::new(stream);
+    client
+        .select(&RouteRef::new("Skills", TRICKY_NAME))
+        .unwrap();
+    assert_eq!(
+        client.get().unwrap(),
+        [RouteRef::new("Skills", TRICKY_NAME)]
+    );
+    assert_eq!(client.list().unwrap().len(), 4);
+}
+
+#[test]
+fn times_out_without_an_answer() {
+    let (robot, computer) = UnixStream::pair().unwrap();
+    computer
+        .set_read_timeout(Some(Duration::from_millis(50)))
+        .unwrap();
+    let mut client = Client::new(computer);
+    let error = client.get().unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+
+    // Waiting for changes outlasts the timeout, even for a line which arrives in pieces
+    let writer = thread::spawn(move || {
+        (&robot).write_all(b"@changed Match/").unwrap();
+        thread::sleep(Duration::from_millis(200));
+        (&robot).write_all(b"Left\n").unwrap();
+        robot
+    });
+    assert_eq!(
+        client.next_change().unwrap(),
+        [RouteRef::new("Match", "Left")]
+    );
+    writer.join().unwrap();
+}
+
+#[test]
+fn answers_bad_requests_with_errors() -> io::Result<()> {
+    let (_robot, mut stream) = RobotThread::start();
+    stream.write_all(b"\r\nfly\nselect Left\nlist now\nget\r\n")?;
+    let mut lines = BufReader::new(stream).lines();
+    let mut next = || {
+        lines
+            .next()
+            .unwrap()
+            .map(|line| Response::parse(&line).unwrap())
+    };
+    assert_eq!(next()?, Response::Err("unknown request `fly`".into()));
+    assert_eq!(
+        next()?,
+        Response::Err("expected `select <category>/<name>`".into())
+    );
+    assert_eq!(
+        next()?,
+        Response::Err("`list` doesn't take an argument".into())
+    );
+    assert_eq!(next()?, Response::Selected(Vec::new()));
+    Ok(())
+}
+
+#[test]
+fn protocol_lines_round_trip() {
+    let requests = [
+        Request::List,
+        Request::Get,
+        Request::Select(RouteRef::new("Red", "Left / Safe")),
+        Request::Subscribe,
+        Request::Unsubscribe,
+        Request::Press(Action::NextGroup),
+        Request::Press(Action::Jump(3)),
+    ];
+    for request in requests {
+        assert_eq!(Request::parse(&request.to_string()), Ok(request));
+    }
+
+    let responses = [
+        Response::Route(RouteRef::new("Red", "Left")),
+        Response::Selected(Vec::new()),
+        Response::Changed(vec![
+            RouteRef::new("Red", "Left"),
+            RouteRef::new("Blue", "Right"),
+        ]),
+        Response::Selected(vec![
+            RouteRef::new("Red/Blue", TRICKY_NAME),
+            RouteRef::new("Red", "Left"),
+        ]),
+        Response::Ok,
+        Response::Err("no route named `Red/Middle`".into()),
+    ];
+    for response in responses {
+        assert_eq!(Response::parse(&response.to_string()), Some(response));
+    }
+    assert_eq!(Response::parse("Starting odometry"), None);
+    // Escaped names stay on one line
+    let line = Response::Route(RouteRef::new("Skills", TRICKY_NAME)).to_string();
+    assert_eq!(line, r"@route Skills/Lap 1\/2\; fast \\ safe\nline");
+}
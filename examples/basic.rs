@@ -3,8 +3,8 @@
 
 extern crate alloc;
 
-use alloc::sync::Arc;
-use core::fmt::Display;
+use alloc::{rc::Rc, sync::Arc};
+use core::{cell::RefCell, fmt::Display};
 
 use autons::prelude::*;
 use autons_controller::prelude::*;
@@ -58,6 +58,7 @@ async fn main(peripherals: Peripherals) {
     robot
         .compete(ControllerSelect::new(
             Arc::new(Mutex::new(peripherals.primary_controller)),
+            Rc::new(RefCell::new(true)),
             [
                 route!(Category::Category1, "Route 1", Robot::route_1),
                 route!(Category::Category2, "Route 2", Robot::route_2),
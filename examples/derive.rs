@@ -0,0 +1,65 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::{rc::Rc, sync::Arc};
+use core::{cell::RefCell, fmt::Display};
+
+use autons::prelude::*;
+use autons_controller::prelude::*;
+use vexide::{prelude::*, sync::Mutex};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Category {
+    Red,
+    Blue,
+}
+impl Display for Category {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Category::Red => write!(f, "Red"),
+            Category::Blue => write!(f, "Blue"),
+        }
+    }
+}
+
+struct Robot {}
+
+impl Robot {
+    async fn red_left(&mut self) {
+        println!("Red left selected");
+    }
+    async fn red_right(&mut self) {
+        println!("Red right selected");
+    }
+    async fn blue_left(&mut self) {
+        println!("Blue left selected");
+    }
+}
+
+#[derive(Routes)]
+#[routes(robot = Robot, category = Category)]
+enum Auton {
+    #[route(category = Category::Red, name = "Left", method = Robot::red_left)]
+    RedLeft,
+    #[route(category = Category::Red, name = "Right", method = Robot::red_right)]
+    RedRight,
+    #[route(category = Category::Blue, name = "Left", method = Robot::blue_left)]
+    BlueLeft,
+}
+
+impl SelectCompete for Robot {}
+
+#[vexide::main]
+async fn main(peripherals: Peripherals) {
+    let robot = Robot {};
+
+    robot
+        .compete(ControllerSelect::new(
+            Arc::new(Mutex::new(peripherals.primary_controller)),
+            Rc::new(RefCell::new(true)),
+            Auton::routes(),
+        ))
+        .await;
+}
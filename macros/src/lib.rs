@@ -0,0 +1,219 @@
+//! Derive macros for `autons-controller`.
+//!
+//! These are re-exported by `autons-controller` when its `derive` feature is enabled, and
+//! shouldn't need to be depended on directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{quote, ToTokens};
+use syn::{
+    parse_macro_input, spanned::Spanned, Data, DeriveInput, Error, Expr, Fields, Ident, LitStr,
+    Path, Type,
+};
+
+/// Generates the route table for an enum with one variant per route.
+///
+/// The enum needs a `#[routes(robot = ..., category = ...)]` attribute naming the robot and
+/// category types, and every variant needs a `#[route(category = ..., name = "...", method =
+/// ...)]` attribute. This adds two methods to the enum:
+///
+/// - `routes()`, which returns an array of every route in declaration order, ready to be passed
+///   to `ControllerSelect`.
+/// - `route(self)`, which returns the route of a single variant.
+///
+/// A variant without a `#[route]` attribute, a missing key, or two variants with the same
+/// category and name are compile errors. Several routes may run the same method, such as a
+/// route mirrored for each side of the field.
+///
+/// Duplicates are found by comparing categories as written, since a macro can't tell what a
+/// path refers to. `Category::Red` and an imported `Red` aren't recognized as the
+/// same category, so keep to one spelling for each.
+///
+/// # Example
+///
+/// ```ignore
+/// #[derive(Routes)]
+/// #[routes(robot = Robot, category = Category)]
+/// enum Auton {
+///     #[route(category = Category::Red, name = "Left", method = Robot::red_left)]
+///     RedLeft,
+///     #[route(category = Category::Blue, name = "Right", method = Robot::blue_right)]
+///     BlueRight,
+/// }
+///
+/// robot.compete(ControllerSelect::new(controller, is_selecting, Auton::routes())).await;
+/// ```
+#[proc_macro_derive(Routes, attributes(routes, route))]
+pub fn derive_routes(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_routes(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+struct RouteAttribute {
+    variant: Ident,
+    category: Expr,
+    name: LitStr,
+    method: Path,
+}
+
+fn expand_routes(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new(
+            Span::call_site(),
+            "`Routes` can only be derived for enums",
+        ));
+    };
+    let (robot, category_type) = parse_routes_attribute(&input)?;
+
+    let mut routes: Vec<RouteAttribute> = Vec::new();
+    let mut errors: Option<Error> = None;
+    let mut push_error = |error: Error| match &mut errors {
+        Some(errors) => errors.combine(error),
+        None => errors = Some(error),
+    };
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            push_error(Error::new(
+                variant.fields.span(),
+                "route variants can't have fields",
+            ));
+            continue;
+        }
+        let route = match parse_route_attribute(variant) {
+            Ok(route) => route,
+            Err(error) => {
+                push_error(error);
+                continue;
+            }
+        };
+
+        // Compared as written, so the same category behind two different paths isn't caught
+        let category = route.category.to_token_stream().to_string();
+        for other in &routes {
+            if other.category.to_token_stream().to_string() == category
+                && other.name.value() == route.name.value()
+            {
+                push_error(Error::new(
+                    route.name.span(),
+                    format!(
+                        "`{}` has the same category and name as `{}`",
+                        route.variant, other.variant
+                    ),
+                ));
+            }
+        }
+        routes.push(route);
+    }
+    if let Some(errors) = errors {
+        return Err(errors);
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let count = routes.len();
+    let variants = routes.iter().map(|route| &route.variant);
+    let arms = routes.iter().map(|route| {
+        let RouteAttribute {
+            variant,
+            category,
+            name,
+            method,
+        } = route;
+        quote! {
+            Self::#variant => ::autons_controller::Route::new(#category, #name, |robot| {
+                ::alloc::boxed::Box::pin(#method(robot))
+            }),
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Returns the route of every variant, in declaration order.
+            pub fn routes() -> [::autons_controller::Route<#robot, #category_type>; #count] {
+                [#(Self::#variants.route()),*]
+            }
+
+            /// Returns the route of this variant.
+            pub fn route(self) -> ::autons_controller::Route<#robot, #category_type> {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    })
+}
+
+fn parse_routes_attribute(input: &DeriveInput) -> syn::Result<(Type, Type)> {
+    let attribute = input
+        .attrs
+        .iter()
+        .find(|attribute| attribute.path().is_ident("routes"))
+        .ok_or_else(|| {
+            Error::new(
+                input.ident.span(),
+                "missing `#[routes(robot = ..., category = ...)]` attribute",
+            )
+        })?;
+
+    let mut robot = None;
+    let mut category = None;
+    attribute.parse_nested_meta(|meta| {
+        if meta.path.is_ident("robot") {
+            robot = Some(meta.value()?.parse::<Type>()?);
+        } else if meta.path.is_ident("category") {
+            category = Some(meta.value()?.parse::<Type>()?);
+        } else {
+            return Err(meta.error("expected `robot` or `category`"));
+        }
+        Ok(())
+    })?;
+
+    match (robot, category) {
+        (Some(robot), Some(category)) => Ok((robot, category)),
+        (None, _) => Err(Error::new(attribute.span(), "missing `robot = ...`")),
+        (_, None) => Err(Error::new(attribute.span(), "missing `category = ...`")),
+    }
+}
+
+fn parse_route_attribute(variant: &syn::Variant) -> syn::Result<RouteAttribute> {
+    let attribute = variant
+        .attrs
+        .iter()
+        .find(|attribute| attribute.path().is_ident("route"))
+        .ok_or_else(|| {
+            Error::new(
+                variant.ident.span(),
+                format!(
+                    "`{}` is missing a `#[route(category = ..., name = \"...\", method = ...)]` \
+                     attribute",
+                    variant.ident
+                ),
+            )
+        })?;
+
+    let mut category = None;
+    let mut name = None;
+    let mut method = None;
+    attribute.parse_nested_meta(|meta| {
+        if meta.path.is_ident("category") {
+            category = Some(meta.value()?.parse::<Expr>()?);
+        } else if meta.path.is_ident("name") {
+            name = Some(meta.value()?.parse::<LitStr>()?);
+        } else if meta.path.is_ident("method") {
+            method = Some(meta.value()?.parse::<Path>()?);
+        } else {
+            return Err(meta.error("expected `category`, `name` or `method`"));
+        }
+        Ok(())
+    })?;
+
+    let missing = |key: &str| Error::new(attribute.span(), format!("missing `{key} = ...`"));
+    Ok(RouteAttribute {
+        variant: variant.ident.clone(),
+        category: category.ok_or_else(|| missing("category"))?,
+        name: name.ok_or_else(|| missing("name"))?,
+        method: method.ok_or_else(|| missing("method"))?,
+    })
+}
@@ -0,0 +1,18 @@
+//! Checks what `#[derive(Routes)]` accepts and the errors it gives, run with `cargo host-test`.
+//!
+//! Run with `TRYBUILD=overwrite` to write new `.stderr` files after an intended change to an
+//! error.
+
+#[test]
+fn derive_routes() {
+    // The cases are built by another cargo, which would otherwise take the robot's `build-std`
+    // from `.cargo/config.toml` instead of the host's from `cargo host-test`
+    std::env::set_var("CARGO_UNSTABLE_BUILD_STD", "std,panic_unwind");
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/ui/routes.rs");
+    cases.pass("tests/ui/shared_method.rs");
+    cases.compile_fail("tests/ui/duplicate_route.rs");
+    cases.compile_fail("tests/ui/missing_route.rs");
+    cases.compile_fail("tests/ui/variant_with_fields.rs");
+    cases.compile_fail("tests/ui/not_an_enum.rs");
+}
@@ -0,0 +1,21 @@
+extern crate alloc;
+
+use autons_controller::Routes;
+
+struct Robot;
+
+impl Robot {
+    async fn left(&mut self) {}
+    async fn right(&mut self) {}
+}
+
+#[derive(Routes)]
+#[routes(robot = Robot, category = &'static str)]
+enum Auton {
+    #[route(category = "Match", name = "Left", method = Robot::left)]
+    Left,
+    #[route(category = "Match", name = "Left", method = Robot::right)]
+    AlsoLeft,
+}
+
+fn main() {}
@@ -0,0 +1,20 @@
+extern crate alloc;
+
+use autons_controller::Routes;
+
+struct Robot;
+
+impl Robot {
+    async fn left(&mut self) {}
+    async fn right(&mut self) {}
+}
+
+#[derive(Routes)]
+#[routes(robot = Robot, category = &'static str)]
+enum Auton {
+    #[route(category = "Match", name = "Left", method = Robot::left)]
+    Left,
+    Right,
+}
+
+fn main() {}
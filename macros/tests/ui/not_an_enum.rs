@@ -0,0 +1,18 @@
+extern crate alloc;
+
+use autons_controller::Routes;
+
+struct Robot;
+
+impl Robot {
+    async fn left(&mut self) {}
+    async fn right(&mut self) {}
+}
+
+#[derive(Routes)]
+#[routes(robot = Robot, category = &'static str)]
+struct Auton {
+    route: u8,
+}
+
+fn main() {}
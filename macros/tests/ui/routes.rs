@@ -0,0 +1,45 @@
+extern crate alloc;
+
+use std::fmt;
+
+use autons_controller::Routes;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Category {
+    Red,
+    Blue,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+struct Robot;
+
+impl Robot {
+    async fn red_left(&mut self) {}
+    async fn red_right(&mut self) {}
+    async fn blue_left(&mut self) {}
+}
+
+#[derive(Clone, Copy, Routes)]
+#[routes(robot = Robot, category = Category)]
+enum Auton {
+    #[route(category = Category::Red, name = "Left", method = Robot::red_left)]
+    RedLeft,
+    #[route(category = Category::Red, name = "Right", method = Robot::red_right)]
+    RedRight,
+    // The same name in another category is a different route
+    #[route(category = Category::Blue, name = "Left", method = Robot::blue_left)]
+    BlueLeft,
+}
+
+fn main() {
+    let routes = Auton::routes();
+    assert_eq!(routes.len(), 3);
+    assert_eq!(routes[2].name, "Left");
+    assert_eq!(routes[2].category, Category::Blue);
+    assert_eq!(Auton::RedRight.route().name, "Right");
+}
@@ -0,0 +1,24 @@
+extern crate alloc;
+
+use autons_controller::Routes;
+
+struct Robot;
+
+impl Robot {
+    async fn side(&mut self) {}
+}
+
+// A route mirrored for each side of the field runs the same method
+#[derive(Clone, Copy, Routes)]
+#[routes(robot = Robot, category = &'static str)]
+enum Auton {
+    #[route(category = "Match", name = "Left", method = Robot::side)]
+    Left,
+    #[route(category = "Match", name = "Right", method = Robot::side)]
+    Right,
+}
+
+fn main() {
+    assert_eq!(Auton::routes().len(), 2);
+    assert_eq!(Auton::Right.route().name, "Right");
+}
@@ -0,0 +1,25 @@
+//! Tries a selector menu in the terminal, without a robot. Run it with `cargo simulate`.
+//!
+//! The menu comes from `routes.rs`, which is written the same way as on a robot. To try a
+//! robot's own menu, point the module at the robot's file with `#[path = "..."]` and give
+//! `Robot` the same route methods. They are never called, since the simulator only prints
+//! which route would run.
+
+extern crate alloc;
+
+mod routes;
+
+/// Stands in for the robot the routes are written for.
+pub struct Robot;
+
+impl Robot {
+    async fn red_left(&mut self) {}
+    async fn red_right(&mut self) {}
+    async fn blue_left(&mut self) {}
+    async fn blue_right(&mut self) {}
+    async fn skills(&mut self) {}
+}
+
+fn main() -> std::io::Result<()> {
+    autons_controller::simulator::run(routes::selector)
+}
@@ -0,0 +1,46 @@
+//! The menu being tried, written the same way as on a robot.
+
+use std::{cell::RefCell, fmt, rc::Rc, time::Duration};
+
+use autons_controller::prelude::*;
+
+use crate::Robot;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Category {
+    Red,
+    Blue,
+    Skills,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Category::Red => write!(f, "Red"),
+            Category::Blue => write!(f, "Blue"),
+            Category::Skills => write!(f, "Skills"),
+        }
+    }
+}
+
+pub fn routes() -> [Route<Robot, Category>; 5] {
+    [
+        route!(Category::Red, "Left", Robot::red_left)
+            .option(RouteOption::integer("Rings", 1, 4, 2)),
+        route!(Category::Red, "Right", Robot::red_right),
+        route!(Category::Blue, "Left", Robot::blue_left)
+            .option(RouteOption::integer("Rings", 1, 4, 2)),
+        route!(Category::Blue, "Right", Robot::blue_right),
+        route!(Category::Skills, "Skills", Robot::skills).timeout(Duration::from_secs(60)),
+    ]
+}
+
+/// Builds the selector, just like the robot does.
+pub fn selector(
+    controller: impl SharedController + 'static,
+    is_selecting: Rc<RefCell<bool>>,
+) -> ControllerSelect<Robot, Category, 5> {
+    ControllerSelect::builder(controller, is_selecting, routes())
+        .start_delay(Duration::from_secs(5))
+        .build()
+}
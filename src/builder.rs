@@ -0,0 +1,363 @@
+//! Builder for configuring a [`ControllerSelect`]
+
+use alloc::{
+    boxed::Box,
+    rc::Rc,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{cell::RefCell, fmt::Display, time::Duration};
+
+use crate::{
+    diagnostics::ExpectedDevice,
+    dial::DialInput,
+    input::InputSource,
+    screen::{Flow, FlowPoint, Insertion, Screen},
+    screens::START_DELAY_STEP,
+    serial::SerialLink,
+    view::Renderer,
+    widgets::{ControllerUi, Timing},
+    Check, ControllerSelect, Keymap, Route, SharedController,
+};
+
+/// Options shared by every step of the selector.
+pub(crate) struct SelectorOptions {
+    pub keymap: Keymap,
+    pub timing: Timing,
+    pub category_prompt: String,
+    pub list_prompt: String,
+    pub cancel_label: String,
+    pub confirm_label: String,
+    pub done_title: String,
+    pub done_message: Option<String>,
+    pub skip_confirm: bool,
+    pub skip_single_category: bool,
+    pub flat_list: bool,
+    pub playlist: bool,
+    pub position_indicator: bool,
+    pub breadcrumbs: bool,
+    pub max_start_delay: Option<Duration>,
+    pub checks: Vec<Check>,
+    pub override_label: String,
+    pub devices: Vec<ExpectedDevice>,
+    pub devices_label: String,
+    pub battery_threshold: Option<f64>,
+    pub dial_debounce: Duration,
+}
+
+impl Default for SelectorOptions {
+    fn default() -> Self {
+        Self {
+            keymap: Keymap::default(),
+            timing: Timing::default(),
+            category_prompt: "- Pick category -".to_string(),
+            list_prompt: "- Pick route -".to_string(),
+            cancel_label: "Cancel".to_string(),
+            confirm_label: "Confirm".to_string(),
+            done_title: "- Route selected -".to_string(),
+            done_message: Some("Good luck!".to_string()),
+            skip_confirm: false,
+            skip_single_category: false,
+            flat_list: false,
+            playlist: false,
+            position_indicator: false,
+            breadcrumbs: false,
+            max_start_delay: None,
+            checks: Vec::new(),
+            override_label: "Override".to_string(),
+            devices: Vec::new(),
+            devices_label: "Devices".to_string(),
+            battery_threshold: None,
+            dial_debounce: Duration::from_millis(150),
+        }
+    }
+}
+
+/// Configures and creates a [`ControllerSelect`].
+///
+/// Created with [`ControllerSelect::builder`]. Any option left unset keeps the same behavior as
+/// [`ControllerSelect::new`].
+pub struct ControllerSelectBuilder<
+    R: 'static,
+    C: Clone + Display + PartialEq + 'static,
+    const N: usize,
+> {
+    controller: Rc<dyn SharedController>,
+    is_selecting: Rc<RefCell<bool>>,
+    routes: [Route<R, C>; N],
+    options: SelectorOptions,
+    insertions: Vec<Insertion<R, C>>,
+    serial: Option<Box<dyn SerialLink>>,
+    dial: Option<Box<dyn DialInput>>,
+    inputs: Vec<Box<dyn InputSource>>,
+    renderers: Vec<Box<dyn Renderer>>,
+}
+
+impl<R, C: Clone + Display + PartialEq + Ord + 'static, const N: usize>
+    ControllerSelectBuilder<R, C, N>
+{
+    pub(crate) fn new(
+        controller: impl SharedController + 'static,
+        is_selecting: Rc<RefCell<bool>>,
+        routes: [Route<R, C>; N],
+    ) -> Self {
+        Self {
+            controller: Rc::new(controller),
+            is_selecting,
+            routes,
+            options: SelectorOptions::default(),
+            insertions: Vec::new(),
+            serial: None,
+            dial: None,
+            inputs: Vec::new(),
+            renderers: Vec::new(),
+        }
+    }
+
+    /// Sets the buttons used to navigate the selector.
+    pub fn keymap(mut self, keymap: Keymap) -> Self {
+        self.options.keymap = keymap;
+        self
+    }
+
+    /// Sets the header shown above the list of categories.
+    pub fn category_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.options.category_prompt = prompt.into();
+        self
+    }
+
+    /// Sets the header shown above the list of routes in [flat list](Self::flat_list) mode.
+    pub fn list_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.options.list_prompt = prompt.into();
+        self
+    }
+
+    /// Sets the labels of the two options on the confirm step.
+    ///
+    /// The route name is appended to `confirm`.
+    pub fn confirm_labels(mut self, cancel: impl Into<String>, confirm: impl Into<String>) -> Self {
+        self.options.cancel_label = cancel.into();
+        self.options.confirm_label = confirm.into();
+        self
+    }
+
+    /// Sets the header shown once a route has been selected.
+    pub fn done_title(mut self, title: impl Into<String>) -> Self {
+        self.options.done_title = title.into();
+        self
+    }
+
+    /// Sets the line shown below the selected route.
+    pub fn done_message(mut self, message: impl Into<String>) -> Self {
+        self.options.done_message = Some(message.into());
+        self
+    }
+
+    /// Hides the line shown below the selected route.
+    pub fn hide_done_message(mut self) -> Self {
+        self.options.done_message = None;
+        self
+    }
+
+    /// Selects a route as soon as it is picked instead of asking for confirmation.
+    pub fn skip_confirm(mut self, skip: bool) -> Self {
+        self.options.skip_confirm = skip;
+        self
+    }
+
+    /// Starts on the route list when every route shares the same category.
+    pub fn skip_single_category(mut self, skip: bool) -> Self {
+        self.options.skip_single_category = skip;
+        self
+    }
+
+    /// Shows every route in a single list instead of picking a category first.
+    ///
+    /// Routes are labelled with their category and grouped in the same order as the category
+    /// list. The [`Keymap::next_group`] and [`Keymap::previous_group`] buttons jump between
+    /// categories.
+    pub fn flat_list(mut self, flat: bool) -> Self {
+        self.options.flat_list = flat;
+        self
+    }
+
+    /// Shows the position of the highlighted entry, such as "3/7", in the header of each list.
+    pub fn position_indicator(mut self, show: bool) -> Self {
+        self.options.position_indicator = show;
+        self
+    }
+
+    /// Titles screens with the path to them, such as "Match > Left > Preload", instead of a
+    /// single prompt.
+    ///
+    /// Long paths are shortened from the front so the current step stays visible.
+    pub fn breadcrumbs(mut self, show: bool) -> Self {
+        self.options.breadcrumbs = show;
+        self
+    }
+
+    /// Lets the driver build a playlist of routes which run back-to-back.
+    ///
+    /// Picked routes are added to the end of the playlist, where they can be moved or removed
+    /// before the playlist is confirmed. The rest of the playlist is skipped if a route
+    /// [times out](Route::timeout) or [fails](crate::RouteContext::fail).
+    pub fn playlist(mut self, playlist: bool) -> Self {
+        self.options.playlist = playlist;
+        self
+    }
+
+    /// Inserts a custom screen into the flow at `point`.
+    ///
+    /// `screen` creates the screen each time the driver reaches `point`. Screens inserted at the
+    /// same point are shown in the order they were added.
+    pub fn screen<S: Screen<R, C> + 'static>(
+        mut self,
+        point: FlowPoint,
+        screen: impl Fn() -> S + 'static,
+    ) -> Self {
+        self.insertions.push(Insertion::new(point, None, screen));
+        self
+    }
+
+    /// Inserts a custom screen which is only shown if `condition` holds when `point` is reached.
+    ///
+    /// The condition can look at the [route being picked](Flow::route) and the
+    /// [answers](Flow::answer) of earlier screens to branch the flow.
+    pub fn screen_if<S: Screen<R, C> + 'static>(
+        mut self,
+        point: FlowPoint,
+        condition: impl Fn(&Flow<R, C>) -> bool + 'static,
+        screen: impl Fn() -> S + 'static,
+    ) -> Self {
+        self.insertions
+            .push(Insertion::new(point, Some(Rc::new(condition)), screen));
+        self
+    }
+
+    /// Asks for a start delay of up to `max` after a route is confirmed.
+    ///
+    /// The delay is picked in half-second steps, so `max` is rounded down to a whole number of
+    /// steps. When the route runs, the selector counts down on the controller for that long
+    /// before calling it.
+    pub fn start_delay(mut self, max: Duration) -> Self {
+        let steps = max.as_millis() / START_DELAY_STEP.as_millis();
+        self.options.max_start_delay = Some(START_DELAY_STEP * steps as u32);
+        self
+    }
+
+    /// Adds a pre-flight check which must pass, or be overridden, before any route is confirmed.
+    pub fn check(mut self, check: Check) -> Self {
+        self.options.checks.push(check);
+        self
+    }
+
+    /// Sets the label of the option that confirms a route despite failed checks.
+    pub fn override_label(mut self, label: impl Into<String>) -> Self {
+        self.options.override_label = label.into();
+        self
+    }
+
+    /// Declares the smart devices the robot needs.
+    ///
+    /// This adds a device health screen to the end of the first list in the selector, and scans
+    /// these devices along with the pre-flight checks before a route is confirmed.
+    pub fn devices(mut self, devices: impl IntoIterator<Item = ExpectedDevice>) -> Self {
+        self.options.devices.extend(devices);
+        self
+    }
+
+    /// Sets the label of the list entry that opens the device health screen.
+    pub fn devices_label(mut self, label: impl Into<String>) -> Self {
+        self.options.devices_label = label.into();
+        self
+    }
+
+    /// Shows the brain and controller battery levels once a route is selected, in place of the
+    /// done message.
+    ///
+    /// If either battery is below `threshold`, from 0 to 1, a blinking warning is shown and the
+    /// controller rumbles.
+    pub fn battery_status(mut self, threshold: f64) -> Self {
+        self.options.battery_threshold = Some(threshold);
+        self
+    }
+
+    /// Sets how often controller input is read.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.options.timing.poll = interval;
+        self
+    }
+
+    /// Sets how long to wait between redraws of the controller screen.
+    ///
+    /// The controller drops writes sent too quickly, so this is never shorter than
+    /// [`Timing::MIN_REDRAW`], which is 200ms. That is also the default.
+    pub fn redraw_interval(mut self, interval: Duration) -> Self {
+        self.options.timing.redraw = interval.max(Timing::MIN_REDRAW);
+        self
+    }
+
+    /// Answers the [serial protocol](crate::serial) on `link`, so routes can be listed and
+    /// selected from a computer.
+    ///
+    /// On the robot, `link` is usually [`serial::Stdio`](crate::serial::Stdio). A route
+    /// selected this way replaces the selection just like [`ControllerSelect::select`].
+    pub fn serial(mut self, link: impl SerialLink + 'static) -> Self {
+        self.serial = Some(Box::new(link));
+        self
+    }
+
+    /// Picks the route with an input on the robot, such as a
+    /// [potentiometer](crate::dial::Potentiometer) or a bank of
+    /// [switches](crate::dial::Switches).
+    ///
+    /// The input maps onto the routes in the order they were declared. Whenever it settles on
+    /// another route, that route replaces the selection and is shown on the controller.
+    pub fn dial(mut self, input: impl DialInput + 'static) -> Self {
+        self.dial = Some(Box::new(input));
+        self
+    }
+
+    /// Sets how long the [dial](Self::dial) has to rest on a route before it is selected.
+    /// Defaults to 150ms.
+    pub fn dial_debounce(mut self, debounce: Duration) -> Self {
+        self.options.dial_debounce = debounce;
+        self
+    }
+
+    /// Adds another input which navigates the selector alongside the controller, such as the
+    /// [brain's touchscreen](crate::input::Touchscreen) or [buttons](crate::input::Buttons) on
+    /// the robot.
+    pub fn input(mut self, input: impl InputSource + 'static) -> Self {
+        self.inputs.push(Box::new(input));
+        self
+    }
+
+    /// Draws the selector somewhere besides the controller, such as the
+    /// [brain's screen](crate::graphics).
+    pub fn renderer(mut self, renderer: impl Renderer + 'static) -> Self {
+        self.renderers.push(Box::new(renderer));
+        self
+    }
+
+    /// Creates the selector and starts its task.
+    pub fn build(self) -> ControllerSelect<R, C, N> {
+        let mut ui = ControllerUi::from_shared(self.controller, self.is_selecting)
+            .with_keymap(self.options.keymap.clone())
+            .with_timing(self.options.timing);
+        for input in self.inputs {
+            ui = ui.with_input(input);
+        }
+        for renderer in self.renderers {
+            ui = ui.with_renderer(renderer);
+        }
+        ControllerSelect::spawn(
+            ui,
+            self.routes,
+            self.options,
+            self.insertions,
+            self.serial,
+            self.dial,
+        )
+    }
+}
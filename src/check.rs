@@ -0,0 +1,55 @@
+//! Pre-flight checks run before a route is confirmed
+
+use alloc::{boxed::Box, rc::Rc, string::String};
+use core::{fmt, future::Future, pin::Pin};
+
+type CheckFn = dyn Fn() -> Pin<Box<dyn Future<Output = bool>>>;
+
+/// A labelled condition which should hold before a route runs, such as "IMU calibrated".
+///
+/// Checks are registered for every route with [`ControllerSelectBuilder::check`] or for a
+/// single route with [`Route::check`]. When the driver picks a route, the selector runs its
+/// checks and lists any that fail, which the driver has to override before confirming.
+///
+/// [`ControllerSelectBuilder::check`]: crate::ControllerSelectBuilder::check
+/// [`Route::check`]: crate::Route::check
+#[derive(Clone)]
+pub struct Check {
+    pub label: String,
+    predicate: Rc<CheckFn>,
+}
+
+impl Check {
+    /// Creates a check from an async predicate which returns `true` if the check passes.
+    pub fn new<F, Fut>(label: impl Into<String>, predicate: F) -> Self
+    where
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = bool> + 'static,
+    {
+        Self {
+            label: label.into(),
+            predicate: Rc::new(move || Box::pin(predicate())),
+        }
+    }
+
+    /// Runs the check, returning `true` if it passed.
+    pub async fn run(&self) -> bool {
+        (self.predicate)().await
+    }
+}
+
+impl fmt::Debug for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Check")
+            .field("label", &self.label)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for Check {
+    fn eq(&self, other: &Self) -> bool {
+        self.label == other.label && Rc::ptr_eq(&self.predicate, &other.predicate)
+    }
+}
+
+impl Eq for Check {}
@@ -0,0 +1,159 @@
+//! Smart port health scanning
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+pub use crate::platform::SmartDeviceType;
+use crate::{
+    platform::{motor_overheating, smart_port_type},
+    view::View,
+    widgets::{ControllerUi, Interaction},
+    Action, Check,
+};
+
+/// A smart device the robot needs in order to run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedDevice {
+    /// Smart port number, from 1 to 21, which [`new`](Self::new) checks.
+    port: u8,
+    pub device_type: SmartDeviceType,
+    /// Short name shown when the device has a problem, such as "Left drive".
+    pub name: String,
+}
+
+impl ExpectedDevice {
+    /// Creates a device expected in a smart port.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `port` isn't from 1 to 21.
+    pub fn new(port: u8, device_type: SmartDeviceType, name: impl Into<String>) -> Self {
+        assert!((1..=21).contains(&port), "Smart port must be from 1 to 21.");
+        Self {
+            port,
+            device_type,
+            name: name.into(),
+        }
+    }
+
+    /// Returns the smart port number, from 1 to 21.
+    pub const fn port(&self) -> u8 {
+        self.port
+    }
+}
+
+/// What is wrong with an [`ExpectedDevice`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeviceProblem {
+    /// Nothing is plugged into the port.
+    Missing,
+    /// A different kind of device is plugged into the port.
+    Mismatched { actual: SmartDeviceType },
+    /// A motor has reached its temperature limit.
+    Overheating { temperature: f64 },
+}
+
+/// A problem found by [`scan_devices`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceIssue {
+    pub device: ExpectedDevice,
+    pub problem: DeviceProblem,
+}
+
+impl DeviceIssue {
+    /// Returns a one-line description of the issue, short enough for the controller.
+    pub fn summary(&self) -> String {
+        let problem = match self.problem {
+            DeviceProblem::Missing => "missing".to_string(),
+            DeviceProblem::Mismatched { actual } => format!("is {:?}", actual),
+            DeviceProblem::Overheating { temperature } => format!("{:.0}C", temperature),
+        };
+        format!("P{} {} {}", self.device.port, self.device.name, problem)
+    }
+}
+
+/// Checks every expected device against what is plugged into the brain.
+///
+/// Motors are also checked for overheating.
+pub fn scan_devices<'a>(
+    expected: impl IntoIterator<Item = &'a ExpectedDevice>,
+) -> Vec<DeviceIssue> {
+    expected
+        .into_iter()
+        .filter_map(|device| {
+            let index = usize::from(device.port - 1);
+            let problem = match smart_port_type(index) {
+                None => DeviceProblem::Missing,
+                Some(actual) if actual != device.device_type => {
+                    DeviceProblem::Mismatched { actual }
+                }
+                Some(SmartDeviceType::Motor) => DeviceProblem::Overheating {
+                    temperature: motor_overheating(index)?,
+                },
+                Some(_) => return None,
+            };
+            Some(DeviceIssue {
+                device: device.clone(),
+                problem,
+            })
+        })
+        .collect()
+}
+
+/// Creates a pre-flight [`Check`] which fails if any of the devices has an issue.
+pub fn device_check(label: impl Into<String>, expected: Vec<ExpectedDevice>) -> Check {
+    Check::new(label, move || {
+        let healthy = scan_devices(&expected).is_empty();
+        async move { healthy }
+    })
+}
+
+/// Scans the expected devices and pages through any issues on the controller.
+///
+/// Returns once the driver confirms or backs out of the screen.
+pub async fn show_device_health(ui: &ControllerUi, expected: &[ExpectedDevice]) {
+    let mut screen = HealthScreen {
+        issues: scan_devices(expected),
+        page: 0,
+    };
+    ui.interact("- Devices -", &mut screen).await;
+}
+
+struct HealthScreen {
+    issues: Vec<DeviceIssue>,
+    page: usize,
+}
+
+impl Interaction for HealthScreen {
+    type Output = ();
+
+    fn handle(&mut self, action: Action) -> Option<()> {
+        let len = self.issues.len().max(1);
+        match action {
+            Action::Next => self.page = (self.page + 1) % len,
+            Action::Previous => self.page = (self.page + len - 1) % len,
+            Action::Jump(page) if page < len => self.page = page,
+            Action::Confirm => return Some(()),
+            _ => {}
+        }
+        None
+    }
+
+    fn view(&self, title: &str) -> View {
+        let (description, supporting) = match self.issues.get(self.page) {
+            Some(issue) => (
+                issue.summary(),
+                Some(format!("< {}/{} >", self.page + 1, self.issues.len())),
+            ),
+            None => ("All devices OK".to_string(), None),
+        };
+        View::Dialog {
+            title: title.to_string(),
+            description,
+            supporting,
+        }
+    }
+}
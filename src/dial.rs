@@ -0,0 +1,186 @@
+//! Picking a route with a dial or switches on the robot
+//!
+//! When a selector is built with [`ControllerSelectBuilder::dial`], an input on the robot picks
+//! from the routes in the order they were declared, even without a working controller. The
+//! selection follows the input live: once it has pointed at another route for the
+//! [debounce time](crate::ControllerSelectBuilder::dial_debounce), that route is selected with
+//! its options at their defaults and shown on the controller. The position the input starts in
+//! isn't selected, so a route picked on the controller, over serial or with
+//! [`ControllerSelect::select`] stays selected until the dial is turned.
+//!
+//! [`ControllerSelectBuilder::dial`]: crate::ControllerSelectBuilder::dial
+//! [`ControllerSelect::select`]: crate::ControllerSelect::select
+
+use alloc::{boxed::Box, rc::Rc, vec::Vec};
+use core::{cell::RefCell, fmt::Display, time::Duration};
+
+#[cfg(target_vendor = "vex")]
+use vexide::devices::adi::{AdiDigitalIn, AdiPotentiometer};
+
+use crate::{
+    platform::{sleep, Instant},
+    SelectorState,
+};
+
+/// An input which points at one of the routes.
+pub trait DialInput {
+    /// Reads which of `count` routes the input points at, or returns `None` if it can't be read
+    /// or doesn't point at a route.
+    ///
+    /// `current` is the route selected by the input so far, which analog inputs can favor near
+    /// the boundary between two routes.
+    fn read(&mut self, count: usize, current: Option<usize>) -> Option<usize>;
+}
+
+impl<T: DialInput + ?Sized> DialInput for Box<T> {
+    fn read(&mut self, count: usize, current: Option<usize>) -> Option<usize> {
+        (**self).read(count, current)
+    }
+}
+
+/// A potentiometer whose travel is split into an equal segment for each route.
+///
+/// Near the boundary between two segments, the reading has to go past the boundary by a
+/// [hysteresis](Self::hysteresis) margin before the next route is picked, so a dial resting on
+/// the boundary doesn't flicker between the two.
+pub struct Potentiometer {
+    angle: Box<dyn FnMut() -> Option<f64>>,
+    max_angle: f64,
+    hysteresis: f64,
+}
+
+impl Potentiometer {
+    /// Creates a potentiometer from a function which reads its angle, from 0 to `max_angle`.
+    pub fn new(max_angle: f64, angle: impl FnMut() -> Option<f64> + 'static) -> Self {
+        assert!(max_angle > 0.0, "Potentiometer range must be positive.");
+        Self {
+            angle: Box::new(angle),
+            max_angle,
+            hysteresis: 0.15,
+        }
+    }
+
+    /// Creates a potentiometer on an ADI port.
+    #[cfg(target_vendor = "vex")]
+    pub fn adi(potentiometer: AdiPotentiometer) -> Self {
+        Self::new(potentiometer.max_angle(), move || {
+            potentiometer.angle().ok()
+        })
+    }
+
+    /// Sets how far past a boundary the reading has to go before the next route is picked, as a
+    /// fraction of a segment. Defaults to 0.15.
+    pub fn hysteresis(mut self, fraction: f64) -> Self {
+        self.hysteresis = fraction.clamp(0.0, 0.5);
+        self
+    }
+}
+
+impl DialInput for Potentiometer {
+    fn read(&mut self, count: usize, current: Option<usize>) -> Option<usize> {
+        let position = ((self.angle)()? / self.max_angle).clamp(0.0, 1.0);
+        let segment = 1.0 / count as f64;
+        let index = ((position / segment) as usize).min(count - 1);
+        match current {
+            Some(current) if current != index && current < count => {
+                let margin = segment * self.hysteresis;
+                let start = current as f64 * segment - margin;
+                let end = (current + 1) as f64 * segment + margin;
+                if (start..end).contains(&position) {
+                    Some(current)
+                } else {
+                    Some(index)
+                }
+            }
+            _ => Some(index),
+        }
+    }
+}
+
+/// A bank of switches read as a binary number, with the first switch as the lowest bit.
+///
+/// Three switches pick from up to eight routes. Numbers past the last route are ignored.
+pub struct Switches {
+    switches: Vec<Box<dyn FnMut() -> Option<bool>>>,
+}
+
+impl Switches {
+    /// Creates a bank of switches from functions which read whether each switch is on, from the
+    /// lowest bit up.
+    pub fn new(switches: impl IntoIterator<Item = Box<dyn FnMut() -> Option<bool>>>) -> Self {
+        Self {
+            switches: switches.into_iter().collect(),
+        }
+    }
+
+    /// Creates a bank of switches on ADI ports, from the lowest bit up.
+    ///
+    /// A switch is on while its input is high, which is while a limit switch is pressed.
+    #[cfg(target_vendor = "vex")]
+    pub fn adi(switches: impl IntoIterator<Item = AdiDigitalIn>) -> Self {
+        Self::new(switches.into_iter().map(|switch| {
+            Box::new(move || switch.is_high().ok()) as Box<dyn FnMut() -> Option<bool>>
+        }))
+    }
+}
+
+impl DialInput for Switches {
+    fn read(&mut self, count: usize, _current: Option<usize>) -> Option<usize> {
+        let mut index = 0;
+        for (bit, switch) in self.switches.iter_mut().enumerate() {
+            if switch()? {
+                index |= 1 << bit;
+            }
+        }
+        (index < count).then_some(index)
+    }
+}
+
+/// Selects the route `input` points at whenever it settles on another one than it started on.
+pub(crate) async fn follow<R, C: Clone + Display + PartialEq + 'static>(
+    mut input: impl DialInput,
+    state: Rc<RefCell<SelectorState<R, C>>>,
+    debounce: Duration,
+    poll: Duration,
+) {
+    let count = state.borrow().routes.len();
+    let mut current = None;
+    // A different route the input has pointed at since the given time
+    let mut pending: Option<(usize, Instant)> = None;
+    loop {
+        if let Some(index) = input.read(count, current) {
+            if Some(index) == current {
+                pending = None;
+            } else {
+                match pending {
+                    Some((pending_index, since)) if pending_index == index => {
+                        if since.elapsed() >= debounce {
+                            // Where the input starts is only a reference, so it doesn't replace a
+                            // route selected some other way before the dial is turned
+                            if current.is_some() {
+                                let mut state = state.borrow_mut();
+                                state.select_index(index);
+                                state.dial_turns = state.dial_turns.wrapping_add(1);
+                            }
+                            current = Some(index);
+                            pending = None;
+                        }
+                    }
+                    _ => pending = Some((index, Instant::now())),
+                }
+            }
+        }
+        sleep(poll).await;
+    }
+}
+
+/// Waits until the dial picks another route than it had when `turns` was read.
+pub(crate) async fn turned<R, C: Clone + Display + PartialEq>(
+    state: &RefCell<SelectorState<R, C>>,
+    turns: u32,
+    poll: Duration,
+) {
+    while state.borrow().dial_turns == turns {
+        sleep(poll).await;
+    }
+}
@@ -1,17 +1,21 @@
 //! Controller display utilities
+//!
+//! The [`ControllerRenderer`] lays out each [`View`] as the controller's three lines of text,
+//! using the helpers here to fit them in the controller's font.
 
 use alloc::{
-    rc::Rc,
+    format,
     string::{String, ToString},
     vec::Vec,
 };
-use core::{cell::RefCell, cmp::min, mem, time::Duration};
+use core::ops::Range;
 
-use futures::FutureExt;
-use vexide::{prelude::Controller, time::sleep};
-
-const SAFE_UPDATE_DURATION: Duration = Duration::from_millis(200);
+use crate::view::{Renderer, View};
 
+/// Returns the width in pixels of a character in the controller's font.
+///
+/// Characters outside the font, such as accented letters or emoji, are counted as wide as `?`
+/// so that names computed at runtime never break the layout.
 pub const fn controller_char_width(letter: char) -> usize {
     match letter {
         'a' => 6,
@@ -109,29 +113,37 @@ pub const fn controller_char_width(letter: char) -> usize {
         '|' => 1,
         '}' => 4,
         '~' => 6,
-        _ => panic!("Invalid letter"),
+        _ => controller_char_width('?'),
     }
 }
 
+/// Returns the width in pixels of a string in the controller's font, including the gap
+/// between characters.
 pub fn controller_str_width(s: &str) -> usize {
-    s.chars().map(controller_char_width).sum::<usize>() + s.len().saturating_sub(1)
+    s.chars().map(controller_char_width).sum::<usize>() + s.chars().count().saturating_sub(1)
+}
+
+/// Returns the width in pixels of the first `count` characters of `s`.
+fn prefix_width(s: &str, count: usize) -> usize {
+    let end = s.char_indices().nth(count).map_or(s.len(), |(i, _)| i);
+    controller_str_width(&s[..end])
 }
 
-const CONTROLLER_WIDTH: usize = 128;
+/// The width in pixels of a line on the controller screen.
+pub const CONTROLLER_WIDTH: usize = 128;
 
-/// Returns a string filled with the provided underlining character
-pub fn underline_string(s: &str, c: char, range: core::ops::Range<usize>) -> String {
+/// Returns a string filled with the provided underlining character below the characters of `s`
+/// in `range`, which counts characters rather than bytes.
+pub fn underline_string(s: &str, c: char, range: Range<usize>) -> String {
     // Find which pixels have the underlined words
-    let underlined_pixels: core::ops::Range<usize> =
-        controller_str_width(&s[0..min(s.len(), range.start)])
-            ..controller_str_width(&s[0..min(s.len(), range.end)]);
+    let underlined_pixels: Range<usize> = prefix_width(s, range.start)..prefix_width(s, range.end);
 
     // Create a new string with the underlined characters
     let mut result = String::new();
     let mut current_width = 0;
     let char_width = controller_char_width(c);
     while current_width < CONTROLLER_WIDTH {
-        if (underlined_pixels.start..(underlined_pixels.end - char_width / 2))
+        if (underlined_pixels.start..underlined_pixels.end.saturating_sub(char_width / 2))
             .contains(&current_width)
         {
             result.push(c);
@@ -145,6 +157,7 @@ pub fn underline_string(s: &str, c: char, range: core::ops::Range<usize>) -> Str
     result
 }
 
+/// Pads a string with spaces so that it is centered on the controller screen.
 pub fn center_string(s: &str) -> String {
     const PADDING_CHAR: char = ' ';
     const PADDING_WIDTH: usize = controller_char_width(PADDING_CHAR) + 1;
@@ -157,130 +170,256 @@ pub fn center_string(s: &str) -> String {
     result
 }
 
-/// Prompts the user to select
-#[allow(clippy::await_holding_refcell_ref)]
-pub async fn horizontal_picker(
-    controller: Rc<RefCell<Controller>>,
-    is_selecting: Rc<RefCell<bool>>,
-    prompt: &str,
-    options: Vec<String>,
-) -> Option<usize> {
-    const ITEM_SEPARATOR: &str = "   ";
+/// Shortens a string to fit in `width` pixels, ending it with ".." if anything was cut off.
+///
+/// Returns an empty string if not even the ".." fits.
+pub fn truncate_string(s: &str, width: usize) -> String {
+    const ELLIPSIS: &str = "..";
+    if controller_str_width(s) <= width {
+        return s.to_string();
+    }
+    if controller_str_width(ELLIPSIS) > width {
+        return String::new();
+    }
+    let mut result = s.to_string();
+    while !result.is_empty()
+        && controller_str_width(&result) + 1 + controller_str_width(ELLIPSIS) > width
+    {
+        result.pop();
+    }
+    result.truncate(result.trim_end().len());
+    result.push_str(ELLIPSIS);
+    result
+}
+
+/// Fits a breadcrumb trail such as "Match > Left > Rings" in `width` pixels.
+///
+/// The leading crumbs are replaced with ".." one at a time until the trail fits, and the last
+/// crumb is truncated if it doesn't fit on its own. Strings without a " > " separator are
+/// simply truncated.
+pub fn fit_breadcrumbs(s: &str, width: usize) -> String {
+    const SEPARATOR: &str = " > ";
+    if controller_str_width(s) <= width {
+        return s.to_string();
+    }
+    let crumbs = s.split(SEPARATOR).collect::<Vec<_>>();
+    for skipped in 1..crumbs.len() {
+        let mut trail = String::from("..");
+        for crumb in &crumbs[skipped..] {
+            trail.push_str(SEPARATOR);
+            trail.push_str(crumb);
+        }
+        if controller_str_width(&trail) <= width {
+            return trail;
+        }
+    }
+    truncate_string(crumbs[crumbs.len() - 1], width)
+}
+
+/// Lays out a header line, with `status` such as "3/7" on the right if it is set.
+///
+/// The title is centered when there is no status, and shortened with [`fit_breadcrumbs`] when
+/// it doesn't fit next to the status.
+pub fn header_string(title: &str, status: Option<&str>) -> String {
+    const PADDING_WIDTH: usize = controller_char_width(' ') + 1;
+    let Some(status) = status else {
+        return center_string(&fit_breadcrumbs(title, CONTROLLER_WIDTH));
+    };
+    // Along with the space before it and the gap between the two
+    let status_width = controller_str_width(status) + PADDING_WIDTH + 1;
+    let mut result = fit_breadcrumbs(title, CONTROLLER_WIDTH.saturating_sub(status_width));
+    let padding = CONTROLLER_WIDTH.saturating_sub(controller_str_width(&result) + status_width)
+        / PADDING_WIDTH;
+    result.push_str(&" ".repeat(padding + 1));
+    result.push_str(status);
+    result
+}
+
+/// Separates the options of a list.
+const LIST_SEPARATOR: &str = "   ";
+/// Shown before a list's options when some are scrolled off to the left.
+const LEFT_MARKER: &str = "< ";
+/// Shown after a list's options when some are scrolled off to the right.
+const RIGHT_MARKER: &str = " >";
+
+/// Picks the characters of `list` that fit on one line with `selected` in view.
+///
+/// Ranges count characters rather than bytes, so names with non-ASCII characters lay out
+/// correctly.
+///
+/// The selected range is centered where possible so there is context on both sides, and the
+/// end of the list is kept against the right edge rather than leaving a gap. An option wider
+/// than the line is shown from its start. Returns the visible range and whether anything is
+/// hidden to the left and to the right, in which case room is left for the overflow markers.
+fn scroll_window(list: &str, selected: Range<usize>) -> (Range<usize>, bool, bool) {
+    let len = list.chars().count();
+    if controller_str_width(list) <= CONTROLLER_WIDTH {
+        return (0..len, false, false);
+    }
+    // The pixel each character starts at, plus one past the end
+    let mut x = Vec::with_capacity(len + 1);
+    let mut pixel = 0;
+    for c in list.chars() {
+        x.push(pixel);
+        pixel += controller_char_width(c) + 1;
+    }
+    x.push(pixel);
+
+    let window = |hidden_left: bool, hidden_right: bool| {
+        let marker_width = controller_str_width(LEFT_MARKER) + 1;
+        let available = CONTROLLER_WIDTH
+            - if hidden_left { marker_width } else { 0 }
+            - if hidden_right { marker_width } else { 0 };
+        let fits = |start: usize, end: usize| x[end] - x[start] <= available + 1;
+
+        let start = if fits(selected.start, selected.end) {
+            let center = (x[selected.start] + x[selected.end]) / 2;
+            let mut start = x
+                .partition_point(|&pixel| pixel < center.saturating_sub(available / 2))
+                .min(selected.start);
+            while start > 0 && fits(start - 1, len) {
+                start -= 1;
+            }
+            start
+        } else {
+            selected.start
+        };
+        let mut end = start;
+        while end < len && fits(start, end + 1) {
+            end += 1;
+        }
+        start..end
+    };
+
+    // Whether the markers are needed depends on the window, so settle on a layout that agrees
+    // with itself, falling back to leaving room for both
+    let mut hidden = (selected.start > 0, true);
+    for _ in 0..3 {
+        let visible = window(hidden.0, hidden.1);
+        let needed = (visible.start > 0, visible.end < len);
+        if needed == hidden {
+            return (visible, needed.0, needed.1);
+        }
+        hidden = needed;
+    }
+    let visible = window(true, true);
+    let needed = (visible.start > 0, visible.end < len);
+    (visible, needed.0, needed.1)
+}
+
+/// Lays out a list as a line with the options side by side, scrolled so `selected` is in
+/// view, and a line underlining it.
+fn list_lines(options: &[String], selected: usize) -> [String; 2] {
     const ITEM_UNDERLINE: char = '^';
 
-    let selected = Rc::new(RefCell::new(0));
+    if options.is_empty() {
+        return Default::default();
+    }
+    let selected = selected.min(options.len() - 1);
+    let list = options.join(LIST_SEPARATOR);
+    let start = options[..selected]
+        .iter()
+        .map(|option| option.chars().count() + LIST_SEPARATOR.len())
+        .sum::<usize>();
+    let selected = start..(start + options[selected].chars().count());
+    let (visible, hidden_left, hidden_right) = scroll_window(&list, selected.clone());
+
+    let mut line = String::new();
+    if hidden_left {
+        line.push_str(LEFT_MARKER);
+    }
+    // Where the visible part of the list starts in the line
+    let shift = line.chars().count();
+    line.extend(list.chars().skip(visible.start).take(visible.len()));
+    // Fill the rest of the line so that nothing from a previous frame is left behind
+    let marker_width = if hidden_right {
+        controller_str_width(RIGHT_MARKER) + 1
+    } else {
+        0
+    };
+    while controller_str_width(&line) + controller_char_width(' ') + 1 + marker_width
+        <= CONTROLLER_WIDTH
     {
-        sleep(SAFE_UPDATE_DURATION).await;
-        _ = controller.borrow_mut().screen.try_clear_screen();
-        sleep(SAFE_UPDATE_DURATION).await;
-        _ = controller
-            .borrow_mut()
-            .screen
-            .try_set_text(center_string(prompt), 1, 1);
+        line.push(' ');
     }
-    futures::select_biased!(
-        x = (async {
-            let selected = selected.clone();
-            loop {
-                {
-                    // Handle button presses
-                    let mut selected = selected.borrow_mut();
-                    let state = controller.borrow_mut().state().unwrap_or_default();
+    if hidden_right {
+        line.push_str(RIGHT_MARKER);
+    }
+
+    let underlined = (selected.start.max(visible.start) - visible.start + shift)
+        ..(selected.end.min(visible.end) - visible.start + shift);
+    let underline = underline_string(&line, ITEM_UNDERLINE, underlined);
+    [line, underline]
+}
 
-                    if state.button_left.is_now_pressed() {
-                        *selected = (*selected + options.len() - 1) % options.len();
-                    } else if state.button_right.is_now_pressed() {
-                        *selected = (*selected + 1) % options.len();
-                    } else if state.button_a.is_now_pressed() {
-                        let selected_ref = *selected;
-                        mem::drop(selected);
-                        sleep(SAFE_UPDATE_DURATION).await;
-                        _ = controller.borrow_mut().screen.try_clear_screen();
-                        break Some(selected_ref);
-                    } else if state.button_b.is_now_pressed() {
-                        mem::drop(selected);
-                        sleep(SAFE_UPDATE_DURATION).await;
-                        _ = controller.borrow_mut().screen.try_clear_screen();
-                        break None;
-                    }
-                    if state.button_power.is_now_pressed() || !*is_selecting.borrow() {
-                        mem::drop(selected);
-                        is_selecting.replace(false);
-                        sleep(SAFE_UPDATE_DURATION).await;
-                        _ = controller.borrow_mut().screen.try_clear_screen();
-                        break None;
+/// Lays out [`View`]s as the controller's three lines of text.
+///
+/// The [`ControllerUi`](crate::widgets::ControllerUi) keeps one of these for the controller and
+/// writes its lines as fast as the controller accepts them, so the controller is drawn from the
+/// same views as every other renderer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ControllerRenderer {
+    lines: [String; 3],
+}
+
+impl ControllerRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-                    }
-                }
-                sleep(Duration::from_millis(20)).await;
+    /// Returns the lines of the last view, from top to bottom. They are all empty for
+    /// [`View::Blank`].
+    pub const fn lines(&self) -> &[String; 3] {
+        &self.lines
+    }
+}
+
+impl Renderer for ControllerRenderer {
+    fn render(&mut self, view: &View) {
+        self.lines = match view {
+            View::Blank => Default::default(),
+            View::List {
+                title,
+                status,
+                options,
+                selected,
+            } => {
+                let [line, underline] = list_lines(options, *selected);
+                [header_string(title, status.as_deref()), line, underline]
             }
-        }).fuse() => { x },
-        () = (async {
-            let selected = selected.clone();
-            loop {
-                {
-                    // Render to the screen
-                    let selected = { *selected.borrow() };
-                    let offset: usize =
-                        options[0..selected]
-                            .iter()
-                            .map(|s| s.len() + ITEM_SEPARATOR.len())
-                            .sum::<usize>()
-                            .saturating_sub(5);
-                    let mut list = options[0..].join(ITEM_SEPARATOR);
-                    list.replace_range(0..offset, "");
-                    let mut at_end = false;
-                    while controller_str_width(&list) < CONTROLLER_WIDTH {
-                        list.push(' ');
-                        at_end = true;
-                    }
-                    while controller_str_width(&list) > CONTROLLER_WIDTH {
-                        list.pop();
-                    }
-                    if !at_end {
-                        list.pop();
-                        list.pop();
-                        list.push_str("> ");
-                    }
-                    _ = controller.borrow_mut().screen.try_set_text(&list, 2, 1);
-                    sleep(SAFE_UPDATE_DURATION).await;
-                    let range_start = options[0..selected]
-                        .iter()
-                        .map(|s| s.len() + ITEM_SEPARATOR.len())
-                        .sum::<usize>() - offset;
-                    let range = range_start..(range_start + options[selected].len());
-                    let underline = underline_string(&list, ITEM_UNDERLINE, range);
-                    _ = controller.borrow_mut().screen.try_set_text(underline, 3, 1);
-                    sleep(SAFE_UPDATE_DURATION).await;
-                }
-                sleep(SAFE_UPDATE_DURATION).await;
+            View::Value {
+                title,
+                value,
+                can_decrease,
+                can_increase,
+            } => {
+                let left = if *can_decrease { "<" } else { " " };
+                let right = if *can_increase { ">" } else { " " };
+                [
+                    header_string(title, None),
+                    center_string(&format!("{left} {value} {right}")),
+                    String::new(),
+                ]
             }
-        }).fuse() => { None },
-    )
+            View::Dialog {
+                title,
+                description,
+                supporting,
+            } => [
+                // A message without a title leaves the first line blank
+                if title.is_empty() {
+                    String::new()
+                } else {
+                    header_string(title, None)
+                },
+                fit_line(description),
+                supporting.as_deref().map(fit_line).unwrap_or_default(),
+            ],
+        };
+    }
 }
 
-pub async fn simple_dialog(
-    controller: Rc<RefCell<Controller>>,
-    _is_selecting: Rc<RefCell<bool>>,
-    title: &str,
-    description: &str,
-    supporting: Option<&str>,
-) {
-    sleep(SAFE_UPDATE_DURATION).await;
-    _ = controller
-        .borrow_mut()
-        .screen
-        .try_set_text(center_string(title), 1, 1);
-    sleep(SAFE_UPDATE_DURATION).await;
-    _ = controller
-        .borrow_mut()
-        .screen
-        .try_set_text(center_string(description), 2, 2);
-    if let Some(supporting) = supporting {
-        sleep(SAFE_UPDATE_DURATION).await;
-        _ = controller
-            .borrow_mut()
-            .screen
-            .try_set_text(center_string(supporting), 3, 2);
-    }
+/// Centers a line of text, shortening it first if it is wider than the screen.
+fn fit_line(s: &str) -> String {
+    center_string(&truncate_string(s, CONTROLLER_WIDTH))
 }
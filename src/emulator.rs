@@ -0,0 +1,670 @@
+//! A virtual controller and brain for running the selector off the robot
+//!
+//! On targets other than the V5, this module stands in for vexide: the selector reads a scripted
+//! [`Controller`] and waits on a virtual clock which only moves forward when every task is
+//! asleep, so a test runs in microseconds and always sees the same timings. The brain's battery,
+//! smart ports and competition state can be set with the functions in this module.
+//!
+//! Every write to the controller's screen is recorded with the time it was made. The screen
+//! can be rendered as text at any point in that history, and checked for writes made closer
+//! together than the real controller accepts.
+//!
+//! ```ignore
+//! let controller = Rc::new(RefCell::new(Controller::new()));
+//! controller.borrow_mut().press(Button::A, Duration::from_millis(500), TAP);
+//! let ui = ControllerUi::new(controller.clone(), Rc::new(RefCell::new(true)));
+//!
+//! let answer = emulator::run_for(Confirm::new("Reset?").show(&ui), Duration::from_secs(1));
+//! assert_eq!(answer, Some(Some(true)));
+//! controller.borrow().screen.assert_rate_limited();
+//! ```
+//!
+//! The clock, tasks and brain are local to the current thread and the clock starts at zero,
+//! so tests running in parallel don't affect each other.
+
+use alloc::{
+    boxed::Box,
+    format,
+    rc::{Rc, Weak},
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    cell::{Cell, RefCell},
+    fmt,
+    future::{pending, Future},
+    ops::Add,
+    pin::{pin, Pin},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use crate::{
+    keymap::{Button, Stick},
+    widgets::SAFE_UPDATE_DURATION,
+};
+
+/// The shortest time the real controller needs between two writes.
+pub const MIN_UPDATE_INTERVAL: Duration = SAFE_UPDATE_DURATION;
+
+/// How long [`Controller::press`] should hold a button for a quick tap.
+pub const TAP: Duration = Duration::from_millis(100);
+
+std::thread_local! {
+    static CLOCK: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+    /// The earliest time a pending [`Sleep`] wants to be woken at.
+    static WAKE_AT: Cell<Option<Instant>> = const { Cell::new(None) };
+    static TASKS: RefCell<Vec<Weak<TaskSlot>>> = const { RefCell::new(Vec::new()) };
+    static BRAIN: RefCell<Brain> = RefCell::new(Brain::default());
+}
+
+/// A point on the virtual clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(Duration);
+
+impl Instant {
+    /// Returns the current virtual time.
+    pub fn now() -> Self {
+        Self(CLOCK.get())
+    }
+
+    /// Returns the virtual time passed since this instant.
+    pub fn elapsed(&self) -> Duration {
+        Self::now().duration_since(*self)
+    }
+
+    /// Returns the virtual time passed from `earlier` to this instant, or zero if `earlier` is
+    /// later.
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        self.0.saturating_sub(earlier.0)
+    }
+
+    /// Returns the time since the clock started.
+    pub const fn since_start(&self) -> Duration {
+        self.0
+    }
+}
+
+impl Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, rhs: Duration) -> Instant {
+        Instant(self.0 + rhs)
+    }
+}
+
+/// Waits until `duration` has passed on the virtual clock.
+pub fn sleep(duration: Duration) -> Sleep {
+    Sleep {
+        until: Instant::now() + duration,
+    }
+}
+
+/// The future returned by [`sleep`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Sleep {
+    until: Instant,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.until {
+            return Poll::Ready(());
+        }
+        let until = self.until;
+        WAKE_AT.set(Some(WAKE_AT.get().map_or(until, |at| at.min(until))));
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+type TaskSlot = RefCell<Option<Pin<Box<dyn Future<Output = ()>>>>>;
+
+/// A task started with [`spawn`], which is cancelled when dropped.
+///
+/// Awaiting the task returns its output once it finishes.
+#[must_use = "dropping a task cancels it"]
+pub struct Task<T> {
+    _future: Rc<TaskSlot>,
+    output: Rc<Cell<Option<T>>>,
+}
+
+impl<T> Future for Task<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<T> {
+        match self.output.take() {
+            Some(output) => Poll::Ready(output),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Task<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Task").finish_non_exhaustive()
+    }
+}
+
+/// Starts running `future` alongside the others on this thread.
+///
+/// Tasks only make progress inside [`run_for`] or [`advance`].
+pub fn spawn<T: 'static>(future: impl Future<Output = T> + 'static) -> Task<T> {
+    let output = Rc::new(Cell::new(None));
+    let task_output = output.clone();
+    let slot: Rc<TaskSlot> = Rc::new(RefCell::new(Some(Box::pin(async move {
+        task_output.set(Some(future.await));
+    }))));
+    TASKS.with_borrow_mut(|tasks| tasks.push(Rc::downgrade(&slot)));
+    Task {
+        _future: slot,
+        output,
+    }
+}
+
+/// Polls every running task once, returning `true` if any of them finished.
+fn poll_tasks(cx: &mut Context<'_>) -> bool {
+    let tasks = TASKS.with_borrow(Clone::clone);
+    let mut finished = false;
+    for task in tasks.iter().filter_map(Weak::upgrade) {
+        let mut slot = task.borrow_mut();
+        if slot
+            .as_mut()
+            .is_some_and(|future| future.as_mut().poll(cx).is_ready())
+        {
+            *slot = None;
+            finished = true;
+        }
+    }
+    TASKS.with_borrow_mut(|tasks| {
+        tasks.retain(|task| task.upgrade().is_some_and(|task| task.borrow().is_some()));
+    });
+    finished
+}
+
+/// Polls `future` to completion along with any spawned tasks, moving the virtual clock forward
+/// whenever they are all asleep.
+///
+/// Returns `None` if the future hasn't finished after `duration` of virtual time, in which
+/// case the clock is left at the deadline. If nothing is waiting on the clock, it jumps
+/// straight to the deadline.
+pub fn run_for<F: Future>(future: F, duration: Duration) -> Option<F::Output> {
+    let deadline = Instant::now() + duration;
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return Some(output);
+        }
+        // A finished task may be what the future is waiting on
+        if poll_tasks(&mut cx) {
+            continue;
+        }
+        match WAKE_AT.take() {
+            Some(wake_at) if wake_at <= deadline => CLOCK.set(wake_at.0),
+            _ => {
+                CLOCK.set(deadline.0);
+                return None;
+            }
+        }
+    }
+}
+
+/// Runs the spawned tasks for `duration` of virtual time.
+pub fn advance(duration: Duration) {
+    _ = run_for(pending::<()>(), duration);
+}
+
+/// A possible type of device that can be plugged into a smart port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmartDeviceType {
+    Motor,
+    Rotation,
+    Imu,
+    Distance,
+    Vision,
+    AiVision,
+    Electromagnet,
+    LightTower,
+    Arm,
+    Optical,
+    Gps,
+    Radio,
+    Adi,
+    GenericSerial,
+    Unknown(u32),
+}
+
+/// The state of the brain outside of the controller.
+#[derive(Debug)]
+struct Brain {
+    battery: f64,
+    competition_connected: bool,
+    /// The device in each smart port, and the temperature of motors at their limit.
+    ports: [Option<(SmartDeviceType, Option<f64>)>; 21],
+}
+
+impl Default for Brain {
+    fn default() -> Self {
+        Self {
+            battery: 1.0,
+            competition_connected: false,
+            ports: [None; 21],
+        }
+    }
+}
+
+/// Sets the brain's battery level from 0 to 1.
+pub fn set_brain_battery(level: f64) {
+    BRAIN.with_borrow_mut(|brain| brain.battery = level);
+}
+
+pub(crate) fn brain_battery() -> f64 {
+    BRAIN.with_borrow(|brain| brain.battery)
+}
+
+/// Plugs the brain into a competition controller or field, or unplugs it.
+pub fn set_competition_connected(connected: bool) {
+    BRAIN.with_borrow_mut(|brain| brain.competition_connected = connected);
+}
+
+pub(crate) fn competition_connected() -> bool {
+    BRAIN.with_borrow(|brain| brain.competition_connected)
+}
+
+/// Plugs a device into a smart port from 1 to 21, or empties the port if `device_type` is
+/// `None`.
+pub fn plug_device(port: u8, device_type: Option<SmartDeviceType>) {
+    assert!((1..=21).contains(&port), "Smart port must be from 1 to 21.");
+    BRAIN.with_borrow_mut(|brain| {
+        brain.ports[usize::from(port - 1)] = device_type.map(|device_type| (device_type, None));
+    });
+}
+
+/// Sets the temperature of the motor in a smart port, at which it has reached its limit, or
+/// cools it down if `temperature` is `None`.
+///
+/// # Panics
+///
+/// Panics if there isn't a motor in the port.
+pub fn set_motor_overheating(port: u8, temperature: Option<f64>) {
+    BRAIN.with_borrow_mut(|brain| match &mut brain.ports[usize::from(port - 1)] {
+        Some((SmartDeviceType::Motor, overheating)) => *overheating = temperature,
+        _ => panic!("There is no motor in port {port}."),
+    });
+}
+
+pub(crate) fn smart_port_type(index: usize) -> Option<SmartDeviceType> {
+    BRAIN.with_borrow(|brain| brain.ports[index].map(|(device_type, _)| device_type))
+}
+
+pub(crate) fn motor_overheating(index: usize) -> Option<f64> {
+    BRAIN.with_borrow(|brain| brain.ports[index].and_then(|(_, temperature)| temperature))
+}
+
+/// An error from the virtual controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControllerError {
+    /// The controller has been disconnected with [`Controller::set_connected`].
+    Offline,
+}
+
+/// Whether a button is held down.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ButtonState {
+    pressed: bool,
+}
+
+impl ButtonState {
+    /// Returns `true` if the button is held down.
+    pub const fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// Returns `true` if the button is not held down.
+    pub const fn is_released(&self) -> bool {
+        !self.pressed
+    }
+}
+
+/// The position of a joystick.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JoystickState {
+    x_raw: i8,
+    y_raw: i8,
+}
+
+impl JoystickState {
+    /// Returns the horizontal position from -1 to 1.
+    pub fn x(&self) -> f64 {
+        f64::from(self.x_raw) / 127.0
+    }
+
+    /// Returns the vertical position from -1 to 1.
+    pub fn y(&self) -> f64 {
+        f64::from(self.y_raw) / 127.0
+    }
+
+    pub const fn x_raw(&self) -> i8 {
+        self.x_raw
+    }
+
+    pub const fn y_raw(&self) -> i8 {
+        self.y_raw
+    }
+}
+
+/// The buttons and joysticks of the virtual controller at one moment.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControllerState {
+    pub left_stick: JoystickState,
+    pub right_stick: JoystickState,
+    pub button_a: ButtonState,
+    pub button_b: ButtonState,
+    pub button_x: ButtonState,
+    pub button_y: ButtonState,
+    pub button_up: ButtonState,
+    pub button_down: ButtonState,
+    pub button_left: ButtonState,
+    pub button_right: ButtonState,
+    pub button_l1: ButtonState,
+    pub button_l2: ButtonState,
+    pub button_r1: ButtonState,
+    pub button_r2: ButtonState,
+    pub button_power: ButtonState,
+}
+
+impl ControllerState {
+    fn button_mut(&mut self, button: Button) -> &mut ButtonState {
+        match button {
+            Button::A => &mut self.button_a,
+            Button::B => &mut self.button_b,
+            Button::X => &mut self.button_x,
+            Button::Y => &mut self.button_y,
+            Button::Up => &mut self.button_up,
+            Button::Down => &mut self.button_down,
+            Button::Left => &mut self.button_left,
+            Button::Right => &mut self.button_right,
+            Button::L1 => &mut self.button_l1,
+            Button::L2 => &mut self.button_l2,
+            Button::R1 => &mut self.button_r1,
+            Button::R2 => &mut self.button_r2,
+            Button::Power => &mut self.button_power,
+        }
+    }
+}
+
+/// What a [`ScreenWrite`] did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WriteKind {
+    /// Text was written to a line, starting at a 1-indexed column.
+    Text { line: u8, column: u8, text: String },
+    /// The whole screen was cleared.
+    Clear,
+    /// The controller was rumbled with a pattern.
+    Rumble(String),
+}
+
+/// A write made to the virtual controller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScreenWrite {
+    /// When the write was made, from the start of the clock.
+    pub at: Duration,
+    pub kind: WriteKind,
+}
+
+impl fmt::Display for ScreenWrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = self.at.as_millis();
+        match &self.kind {
+            WriteKind::Text { line, column, text } => {
+                write!(f, "{at}ms: line {line}, column {column}: {text:?}")
+            }
+            WriteKind::Clear => write!(f, "{at}ms: clear"),
+            WriteKind::Rumble(pattern) => write!(f, "{at}ms: rumble {pattern:?}"),
+        }
+    }
+}
+
+/// The three-line screen of the virtual controller, with a log of every write made to it.
+#[derive(Debug, Default, Clone)]
+pub struct ControllerScreen {
+    writes: Vec<ScreenWrite>,
+    connected: bool,
+}
+
+impl ControllerScreen {
+    pub const MAX_COLUMNS: usize = 19;
+    pub const MAX_LINES: usize = 3;
+
+    /// Writes text to a line, replacing everything from `column` to the end of the line.
+    ///
+    /// Line 4 is the rumble motor, like on the real controller.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line` or `column` is out of range.
+    pub fn try_set_text(
+        &mut self,
+        text: impl AsRef<str>,
+        line: u8,
+        column: u8,
+    ) -> Result<(), ControllerError> {
+        if !self.connected {
+            return Err(ControllerError::Offline);
+        }
+        assert!(
+            column != 0 && usize::from(column) <= Self::MAX_COLUMNS,
+            "Invalid column number ({column})"
+        );
+        let kind = match line {
+            1..=3 => WriteKind::Text {
+                line,
+                column,
+                text: text.as_ref().to_string(),
+            },
+            4 => WriteKind::Rumble(text.as_ref().to_string()),
+            _ => panic!("Invalid line number ({line})"),
+        };
+        self.record(kind);
+        Ok(())
+    }
+
+    /// Clears every line of the screen.
+    pub fn try_clear_screen(&mut self) -> Result<(), ControllerError> {
+        if !self.connected {
+            return Err(ControllerError::Offline);
+        }
+        self.record(WriteKind::Clear);
+        Ok(())
+    }
+
+    fn record(&mut self, kind: WriteKind) {
+        self.writes.push(ScreenWrite {
+            at: Instant::now().since_start(),
+            kind,
+        });
+    }
+
+    /// Returns every write made so far, oldest first.
+    pub fn writes(&self) -> &[ScreenWrite] {
+        &self.writes
+    }
+
+    /// Renders the screen as it is now, one line of text per screen line.
+    pub fn frame(&self) -> String {
+        self.frame_at(Instant::now().since_start())
+    }
+
+    /// Renders the screen as it was at `at` from the start of the clock.
+    ///
+    /// Trailing spaces are trimmed from each line.
+    pub fn frame_at(&self, at: Duration) -> String {
+        let mut lines: [Vec<char>; 3] = Default::default();
+        for write in self.writes.iter().take_while(|write| write.at <= at) {
+            match &write.kind {
+                WriteKind::Text { line, column, text } => {
+                    let line = &mut lines[usize::from(*line) - 1];
+                    let start = usize::from(*column) - 1;
+                    line.resize(start.max(line.len()), ' ');
+                    line.truncate(start);
+                    line.extend(text.chars());
+                }
+                WriteKind::Clear => lines.iter_mut().for_each(Vec::clear),
+                WriteKind::Rumble(_) => {}
+            }
+        }
+        lines
+            .iter()
+            .map(|line| line.iter().collect::<String>().trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Returns every pair of consecutive writes made less than [`MIN_UPDATE_INTERVAL`] apart.
+    pub fn rate_violations(&self) -> Vec<(&ScreenWrite, &ScreenWrite)> {
+        self.writes
+            .windows(2)
+            .filter(|pair| pair[1].at.saturating_sub(pair[0].at) < MIN_UPDATE_INTERVAL)
+            .map(|pair| (&pair[0], &pair[1]))
+            .collect()
+    }
+
+    /// Panics with a list of the offending writes if any were made too close together.
+    #[track_caller]
+    pub fn assert_rate_limited(&self) {
+        let violations = self.rate_violations();
+        if !violations.is_empty() {
+            let list = violations
+                .iter()
+                .map(|(earlier, later)| format!("  {earlier}\n  {later}\n"))
+                .collect::<String>();
+            panic!(
+                "{} writes were made less than {}ms apart:\n{list}",
+                violations.len(),
+                MIN_UPDATE_INTERVAL.as_millis()
+            );
+        }
+    }
+}
+
+/// A button press scheduled on the virtual clock.
+#[derive(Debug, Clone, Copy)]
+struct Press {
+    button: Button,
+    from: Duration,
+    until: Duration,
+}
+
+/// A joystick pushed sideways, scheduled on the virtual clock.
+#[derive(Debug, Clone, Copy)]
+struct Tilt {
+    stick: Stick,
+    x_raw: i8,
+    from: Duration,
+    until: Duration,
+}
+
+/// A controller whose inputs are scripted ahead of time.
+#[derive(Debug, Clone)]
+pub struct Controller {
+    pub screen: ControllerScreen,
+    presses: Vec<Press>,
+    tilts: Vec<Tilt>,
+    battery: f64,
+}
+
+impl Default for Controller {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Controller {
+    /// Creates a connected controller with a full battery and no buttons pressed.
+    pub fn new() -> Self {
+        Self {
+            screen: ControllerScreen {
+                writes: Vec::new(),
+                connected: true,
+            },
+            presses: Vec::new(),
+            tilts: Vec::new(),
+            battery: 1.0,
+        }
+    }
+
+    /// Holds `button` down from `at` after the start of the clock for `hold`.
+    pub fn press(&mut self, button: Button, at: Duration, hold: Duration) -> &mut Self {
+        self.presses.push(Press {
+            button,
+            from: at,
+            until: at + hold,
+        });
+        self
+    }
+
+    /// Pushes `stick` sideways to `x`, from -1 to 1, from `at` after the start of the clock for
+    /// `hold`. A later tilt of the same stick takes over where the two overlap.
+    pub fn tilt(&mut self, stick: Stick, x: f64, at: Duration, hold: Duration) -> &mut Self {
+        self.tilts.push(Tilt {
+            stick,
+            x_raw: (x.clamp(-1.0, 1.0) * 127.0) as i8,
+            from: at,
+            until: at + hold,
+        });
+        self
+    }
+
+    /// Sets the battery level from 0 to 1.
+    pub fn set_battery(&mut self, level: f64) -> &mut Self {
+        self.battery = level;
+        self
+    }
+
+    /// Connects or disconnects the controller. Every read and write fails while disconnected.
+    pub fn set_connected(&mut self, connected: bool) -> &mut Self {
+        self.screen.connected = connected;
+        self
+    }
+
+    /// Returns the scripted state of the buttons and joysticks at the current virtual time.
+    pub fn state(&self) -> Result<ControllerState, ControllerError> {
+        if !self.screen.connected {
+            return Err(ControllerError::Offline);
+        }
+        let now = Instant::now().since_start();
+        let mut state = ControllerState::default();
+        for press in &self.presses {
+            if (press.from..press.until).contains(&now) {
+                state.button_mut(press.button).pressed = true;
+            }
+        }
+        for tilt in &self.tilts {
+            if (tilt.from..tilt.until).contains(&now) {
+                let joystick = match tilt.stick {
+                    Stick::Left => &mut state.left_stick,
+                    Stick::Right => &mut state.right_stick,
+                };
+                joystick.x_raw = tilt.x_raw;
+            }
+        }
+        Ok(state)
+    }
+
+    /// Returns the battery level from 0 to 1.
+    pub fn battery_capacity(&self) -> Result<f64, ControllerError> {
+        if !self.screen.connected {
+            return Err(ControllerError::Offline);
+        }
+        Ok(self.battery)
+    }
+
+    /// Rumbles the controller, which is recorded as a write to the screen.
+    pub fn try_rumble(&mut self, pattern: impl AsRef<str>) -> Result<(), ControllerError> {
+        self.screen.try_set_text(pattern, 4, 1)
+    }
+}
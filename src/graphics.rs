@@ -0,0 +1,401 @@
+//! Draws the selector on the brain's screen, or any other `embedded-graphics` display
+//!
+//! A [`GraphicsRenderer`] draws each [`View`] onto a [`DrawTarget`] with a bitmap font: a header
+//! bar with the title, lists with the selected option highlighted, values with arrows and
+//! dialogs. Add it with [`ControllerSelectBuilder::renderer`] to show the same flow on a display
+//! as on the controller. On the robot, the display is usually a [`BrainDisplay`].
+//!
+//! This module needs the `graphics` feature.
+//!
+//! [`ControllerSelectBuilder::renderer`]: crate::ControllerSelectBuilder::renderer
+
+use alloc::{format, string::String};
+
+use embedded_graphics::{
+    mono_font::{ascii::FONT_10X20, MonoFont, MonoTextStyle},
+    pixelcolor::Rgb888,
+    prelude::*,
+    primitives::Rectangle,
+    text::{Alignment, Baseline, Text, TextStyleBuilder},
+};
+#[cfg(target_vendor = "vex")]
+use vexide::devices::display::Display;
+
+use crate::view::{Renderer, View};
+
+/// Colors and font used by a [`GraphicsRenderer`].
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub background: Rgb888,
+    pub text: Rgb888,
+    /// Background of the header bar.
+    pub header: Rgb888,
+    pub header_text: Rgb888,
+    /// Background of the selected option.
+    pub highlight: Rgb888,
+    pub highlight_text: Rgb888,
+    /// Secondary text and the scrollbar.
+    pub dim: Rgb888,
+    pub font: &'static MonoFont<'static>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            background: Rgb888::BLACK,
+            text: Rgb888::WHITE,
+            header: Rgb888::new(0x30, 0x30, 0x30),
+            header_text: Rgb888::WHITE,
+            highlight: Rgb888::new(0x1e, 0x6f, 0xd9),
+            highlight_text: Rgb888::WHITE,
+            dim: Rgb888::new(0x80, 0x80, 0x80),
+            font: &FONT_10X20,
+        }
+    }
+}
+
+/// Draws [`View`]s onto an `embedded-graphics` [`DrawTarget`].
+///
+/// Every view is drawn over the whole target, which is cleared first. Errors from the target are
+/// ignored, since there is nowhere to report them.
+pub struct GraphicsRenderer<D> {
+    target: D,
+    theme: Theme,
+    /// The first option shown when a list is too long for the screen
+    scroll: usize,
+}
+
+impl<D: DrawTarget> GraphicsRenderer<D>
+where
+    D::Color: From<Rgb888>,
+{
+    pub fn new(target: D) -> Self {
+        Self {
+            target,
+            theme: Theme::default(),
+            scroll: 0,
+        }
+    }
+
+    /// Sets the colors and font. Defaults to [`Theme::default`].
+    pub const fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    pub const fn target(&self) -> &D {
+        &self.target
+    }
+
+    pub const fn target_mut(&mut self) -> &mut D {
+        &mut self.target
+    }
+
+    pub fn into_inner(self) -> D {
+        self.target
+    }
+
+    /// Width of a character in pixels.
+    fn char_width(&self) -> u32 {
+        self.theme.font.character_size.width + self.theme.font.character_spacing
+    }
+
+    /// Height of a header or list row in pixels.
+    fn row_height(&self) -> u32 {
+        let height = self.theme.font.character_size.height;
+        height + 2 * (height / 5)
+    }
+
+    /// Space left between text and the sides of the screen.
+    fn margin(&self) -> u32 {
+        self.theme.font.character_size.width / 2
+    }
+
+    fn style(&self, color: Rgb888) -> MonoTextStyle<'static, D::Color> {
+        MonoTextStyle::new(self.theme.font, color.into())
+    }
+
+    /// Shortens `text` to fit in `width` pixels.
+    fn fit(&self, text: &str, width: u32) -> String {
+        let max = (width / self.char_width()) as usize;
+        if text.chars().count() <= max {
+            return text.into();
+        }
+        let mut fitted: String = text.chars().take(max.saturating_sub(2)).collect();
+        fitted.push_str(&".."[..max.min(2)]);
+        fitted
+    }
+
+    /// Draws `text` on the row starting at `top`, aligned within `area`.
+    fn text(
+        &mut self,
+        text: &str,
+        area: Rectangle,
+        top: i32,
+        alignment: Alignment,
+        color: Rgb888,
+    ) -> Result<(), D::Error> {
+        let text = self.fit(text, area.size.width);
+        let x = match alignment {
+            Alignment::Left => area.top_left.x,
+            Alignment::Center => area.top_left.x + area.size.width as i32 / 2,
+            Alignment::Right => area.top_left.x + area.size.width as i32,
+        };
+        let y = top + ((self.row_height() - self.theme.font.character_size.height) / 2) as i32;
+        let text_style = TextStyleBuilder::new()
+            .alignment(alignment)
+            .baseline(Baseline::Top)
+            .build();
+        Text::with_text_style(&text, Point::new(x, y), self.style(color), text_style)
+            .draw(&mut self.target)?;
+        Ok(())
+    }
+
+    fn fill(&mut self, area: Rectangle, color: Rgb888) -> Result<(), D::Error> {
+        self.target.fill_solid(&area, color.into())
+    }
+
+    /// Draws the header bar, returning the area left below it.
+    fn header(&mut self, title: &str, status: Option<&str>) -> Result<Rectangle, D::Error> {
+        let bounds = self.target.bounding_box();
+        let row = self.row_height();
+        self.fill(
+            Rectangle::new(bounds.top_left, Size::new(bounds.size.width, row)),
+            self.theme.header,
+        )?;
+
+        let margin = self.margin();
+        let mut area = Rectangle::new(
+            bounds.top_left + Point::new(margin as i32, 0),
+            Size::new(bounds.size.width.saturating_sub(2 * margin), row),
+        );
+        if let Some(status) = status {
+            self.text(
+                status,
+                area,
+                bounds.top_left.y,
+                Alignment::Right,
+                self.theme.header_text,
+            )?;
+            let used = (status.chars().count() as u32 + 1) * self.char_width();
+            area.size.width = area.size.width.saturating_sub(used);
+        }
+        self.text(
+            title,
+            area,
+            bounds.top_left.y,
+            Alignment::Left,
+            self.theme.header_text,
+        )?;
+
+        Ok(Rectangle::new(
+            bounds.top_left + Point::new(0, row as i32),
+            Size::new(bounds.size.width, bounds.size.height.saturating_sub(row)),
+        ))
+    }
+
+    fn list(
+        &mut self,
+        body: Rectangle,
+        options: &[String],
+        selected: usize,
+    ) -> Result<(), D::Error> {
+        let row = self.row_height();
+        let visible = ((body.size.height / row) as usize).max(1);
+        // Only scroll once the selection leaves the screen, like the controller does
+        if selected < self.scroll {
+            self.scroll = selected;
+        } else if selected >= self.scroll + visible {
+            self.scroll = selected + 1 - visible;
+        }
+        self.scroll = self.scroll.min(options.len().saturating_sub(visible));
+
+        let margin = self.margin();
+        let scrollbar = options.len() > visible;
+        let text_width = body
+            .size
+            .width
+            .saturating_sub(2 * margin + if scrollbar { margin } else { 0 });
+
+        for (i, option) in options.iter().enumerate().skip(self.scroll).take(visible) {
+            let top = body.top_left.y + ((i - self.scroll) as u32 * row) as i32;
+            let color = if i == selected {
+                let width = body
+                    .size
+                    .width
+                    .saturating_sub(if scrollbar { margin } else { 0 });
+                self.fill(
+                    Rectangle::new(Point::new(body.top_left.x, top), Size::new(width, row)),
+                    self.theme.highlight,
+                )?;
+                self.theme.highlight_text
+            } else {
+                self.theme.text
+            };
+            let area = Rectangle::new(
+                Point::new(body.top_left.x + margin as i32, top),
+                Size::new(text_width, row),
+            );
+            self.text(option, area, top, Alignment::Left, color)?;
+        }
+
+        if scrollbar {
+            let height = body.size.height;
+            let thumb_top = height * self.scroll as u32 / options.len() as u32;
+            let thumb_height = (height * visible as u32 / options.len() as u32).max(1);
+            self.fill(
+                Rectangle::new(
+                    body.top_left + Point::new((body.size.width - margin) as i32, thumb_top as i32),
+                    Size::new(margin, thumb_height),
+                ),
+                self.theme.dim,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Draws up to two rows of text centered in `body`.
+    fn centered(
+        &mut self,
+        body: Rectangle,
+        main: &str,
+        supporting: Option<&str>,
+    ) -> Result<(), D::Error> {
+        let row = self.row_height() as i32;
+        let rows = if supporting.is_some() { 2 } else { 1 };
+        let top = body.top_left.y + (body.size.height as i32 - rows * row) / 2;
+        let margin = self.margin();
+        let area = Rectangle::new(
+            body.top_left + Point::new(margin as i32, 0),
+            Size::new(body.size.width.saturating_sub(2 * margin), body.size.height),
+        );
+        self.text(main, area, top, Alignment::Center, self.theme.text)?;
+        if let Some(supporting) = supporting {
+            self.text(
+                supporting,
+                area,
+                top + row,
+                Alignment::Center,
+                self.theme.dim,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, view: &View) -> Result<(), D::Error> {
+        self.target.clear(self.theme.background.into())?;
+        match view {
+            View::Blank => {}
+            View::List {
+                title,
+                status,
+                options,
+                selected,
+            } => {
+                let body = self.header(title, status.as_deref())?;
+                self.list(body, options, *selected)?;
+            }
+            View::Value {
+                title,
+                value,
+                can_decrease,
+                can_increase,
+            } => {
+                let body = self.header(title, None)?;
+                let left = if *can_decrease { "<" } else { " " };
+                let right = if *can_increase { ">" } else { " " };
+                self.centered(body, &format!("{left} {value} {right}"), None)?;
+            }
+            View::Dialog {
+                title,
+                description,
+                supporting,
+            } => {
+                let body = if title.is_empty() {
+                    self.target.bounding_box()
+                } else {
+                    self.header(title, None)?
+                };
+                self.centered(body, description, supporting.as_deref())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<D: DrawTarget> Renderer for GraphicsRenderer<D>
+where
+    D::Color: From<Rgb888>,
+{
+    fn render(&mut self, view: &View) {
+        _ = self.draw(view);
+    }
+}
+
+/// The brain's screen as a [`DrawTarget`], below the program header.
+#[cfg(target_vendor = "vex")]
+pub struct BrainDisplay {
+    _display: Display,
+}
+
+#[cfg(target_vendor = "vex")]
+impl BrainDisplay {
+    /// Takes over the brain's screen from the [`Display`] in the robot's peripherals.
+    pub const fn new(display: Display) -> Self {
+        Self { _display: display }
+    }
+}
+
+#[cfg(target_vendor = "vex")]
+impl OriginDimensions for BrainDisplay {
+    fn size(&self) -> Size {
+        Size::new(
+            Display::HORIZONTAL_RESOLUTION as u32,
+            Display::VERTICAL_RESOLUTION as u32,
+        )
+    }
+}
+
+#[cfg(target_vendor = "vex")]
+impl DrawTarget for BrainDisplay {
+    type Color = Rgb888;
+    type Error = core::convert::Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let bounds = self.bounding_box();
+        for Pixel(point, color) in pixels {
+            if !bounds.contains(point) {
+                continue;
+            }
+            unsafe {
+                vex_sdk::vexDisplayForegroundColor(color.into_storage());
+                vex_sdk::vexDisplayPixelSet(
+                    point.x as u32,
+                    (point.y + i32::from(Display::HEADER_HEIGHT)) as u32,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let area = area.intersection(&self.bounding_box());
+        let Some(bottom_right) = area.bottom_right() else {
+            return Ok(());
+        };
+        let offset = i32::from(Display::HEADER_HEIGHT);
+        unsafe {
+            vex_sdk::vexDisplayForegroundColor(color.into_storage());
+            vex_sdk::vexDisplayRectFill(
+                area.top_left.x,
+                area.top_left.y + offset,
+                bottom_right.x,
+                bottom_right.y + offset,
+            );
+        }
+        Ok(())
+    }
+}
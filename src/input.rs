@@ -0,0 +1,323 @@
+//! Navigation input from the controller, the brain's touchscreen, ADI buttons and more
+//!
+//! Widgets never read buttons themselves. Every input is an [`InputSource`] which turns its own
+//! readings into [`Action`]s, and the [`ControllerUi`] merges all of them into a single stream
+//! for whichever widget is open. The controller is always an input, and others are added with
+//! [`ControllerSelectBuilder::input`] or [`ControllerUi::with_input`].
+//!
+//! [`ControllerUi`]: crate::widgets::ControllerUi
+//! [`ControllerUi::with_input`]: crate::widgets::ControllerUi::with_input
+//! [`ControllerSelectBuilder::input`]: crate::ControllerSelectBuilder::input
+
+use alloc::{boxed::Box, collections::VecDeque, rc::Rc, vec::Vec};
+use core::{cell::RefCell, time::Duration};
+
+#[cfg(target_vendor = "vex")]
+use vexide::devices::adi::AdiDigitalIn;
+
+use crate::{
+    keymap::ActionReader,
+    platform::{ControllerState, Instant},
+    Action, Keymap, SharedController,
+};
+
+/// Something which produces navigation [`Action`]s.
+pub trait InputSource {
+    /// Returns the next action produced since the last poll, if any.
+    ///
+    /// Sources are polled together at the UI's poll interval.
+    fn poll(&mut self) -> Option<Action>;
+
+    /// Ignores inputs which are held right now until they are released, so a press that closed
+    /// one widget doesn't leak into the next. This is called whenever a widget opens.
+    fn reset(&mut self) {}
+}
+
+impl<T: InputSource + ?Sized> InputSource for Box<T> {
+    fn poll(&mut self) -> Option<Action> {
+        (**self).poll()
+    }
+
+    fn reset(&mut self) {
+        (**self).reset();
+    }
+}
+
+/// The controller's buttons and joystick, read according to a [`Keymap`].
+pub(crate) struct ControllerInput {
+    controller: Rc<dyn SharedController>,
+    reader: ActionReader,
+}
+
+impl ControllerInput {
+    pub fn new(controller: Rc<dyn SharedController>, keymap: Keymap) -> Self {
+        Self {
+            controller,
+            reader: ActionReader::new(keymap),
+        }
+    }
+}
+
+impl InputSource for ControllerInput {
+    fn poll(&mut self) -> Option<Action> {
+        let mut state: Option<ControllerState> = None;
+        self.controller
+            .try_with(&mut |controller| state = Some(controller.state().unwrap_or_default()));
+        self.reader.update(&state?)
+    }
+
+    fn reset(&mut self) {
+        self.reader.reset();
+    }
+}
+
+/// A debounced on or off signal, such as whether a switch is pressed.
+#[derive(Debug, Clone, Copy, Default)]
+struct Level {
+    on: bool,
+    /// When the signal started reading differently from `on`
+    changing_since: Option<Instant>,
+}
+
+impl Level {
+    /// A signal which is on right now, without waiting for the debounce time.
+    const fn held() -> Self {
+        Self {
+            on: true,
+            changing_since: None,
+        }
+    }
+
+    /// Feeds a reading in, returning the new level once a change has held for `debounce`.
+    fn update(&mut self, reading: bool, debounce: Duration) -> Option<bool> {
+        if reading == self.on {
+            self.changing_since = None;
+            return None;
+        }
+        let since = *self.changing_since.get_or_insert_with(Instant::now);
+        if since.elapsed() < debounce {
+            return None;
+        }
+        self.on = reading;
+        self.changing_since = None;
+        Some(reading)
+    }
+}
+
+struct Button {
+    action: Action,
+    read: Box<dyn FnMut() -> Option<bool>>,
+    level: Level,
+    /// Set while a press from before the last reset is still held
+    ignored: bool,
+}
+
+/// Buttons on the robot, such as limit switches or bumpers on ADI ports, which each trigger an
+/// action when pressed.
+///
+/// A press or release only counts once the button has read the same for the
+/// [debounce time](Self::debounce), so a bouncing contact triggers once.
+pub struct Buttons {
+    buttons: Vec<Button>,
+    debounce: Duration,
+    queue: VecDeque<Action>,
+}
+
+impl Buttons {
+    pub fn new() -> Self {
+        Self {
+            buttons: Vec::new(),
+            debounce: Duration::from_millis(30),
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// Adds a button which triggers `action`, read by a function which returns whether it is
+    /// pressed, or `None` if it can't be read.
+    pub fn button(mut self, action: Action, read: impl FnMut() -> Option<bool> + 'static) -> Self {
+        self.buttons.push(Button {
+            action,
+            read: Box::new(read),
+            level: Level::default(),
+            ignored: false,
+        });
+        self
+    }
+
+    /// Adds a button on an ADI port which triggers `action` while its input is high, which is
+    /// while a limit switch or bumper is pressed.
+    #[cfg(target_vendor = "vex")]
+    pub fn adi(self, action: Action, input: AdiDigitalIn) -> Self {
+        self.button(action, move || input.is_high().ok())
+    }
+
+    /// Sets how long a button has to read the same before a press or release counts. Defaults
+    /// to 30ms.
+    pub const fn debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+}
+
+impl Default for Buttons {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputSource for Buttons {
+    fn poll(&mut self) -> Option<Action> {
+        for button in &mut self.buttons {
+            let Some(reading) = (button.read)() else {
+                continue;
+            };
+            match button.level.update(reading, self.debounce) {
+                Some(true) if !button.ignored => self.queue.push_back(button.action),
+                Some(false) => button.ignored = false,
+                _ => {}
+            }
+        }
+        self.queue.pop_front()
+    }
+
+    fn reset(&mut self) {
+        self.queue.clear();
+        for button in &mut self.buttons {
+            if (button.read)() == Some(true) {
+                button.level = Level::held();
+            }
+            button.ignored = button.level.on;
+        }
+    }
+}
+
+/// The brain's touchscreen, split into three columns.
+///
+/// Tapping the left column triggers [`Action::Previous`], the middle [`Action::Confirm`] and the
+/// right [`Action::Next`]. Holding anywhere for the [hold time](Self::hold) triggers
+/// [`Action::Back`] instead.
+pub struct Touchscreen {
+    read: Box<dyn FnMut() -> Option<(i16, i16)>>,
+    level: Level,
+    debounce: Duration,
+    hold: Duration,
+    /// Where and when the current touch started
+    touch: Option<(i16, Instant)>,
+    /// Set once the current touch has triggered, or if it started before the last reset
+    handled: bool,
+}
+
+impl Touchscreen {
+    /// The width of the brain's screen in pixels.
+    pub const WIDTH: i16 = 480;
+
+    /// Creates a touchscreen from a function which returns the point being touched, or `None`
+    /// if the screen isn't being touched.
+    pub fn new(read: impl FnMut() -> Option<(i16, i16)> + 'static) -> Self {
+        Self {
+            read: Box::new(read),
+            level: Level::default(),
+            debounce: Duration::from_millis(20),
+            hold: Duration::from_millis(600),
+            touch: None,
+            handled: false,
+        }
+    }
+
+    /// Reads the brain's touchscreen.
+    #[cfg(target_vendor = "vex")]
+    pub fn brain() -> Self {
+        use vex_sdk::{vexTouchDataGet, V5_TouchEvent, V5_TouchStatus};
+
+        Self::new(|| {
+            let mut status = V5_TouchStatus::default();
+            unsafe {
+                vexTouchDataGet(&mut status);
+            }
+            (status.lastEvent != V5_TouchEvent::kTouchEventRelease)
+                .then_some((status.lastXpos, status.lastYpos))
+        })
+    }
+
+    /// Sets how long a touch is held to go back. Defaults to 600ms.
+    pub const fn hold(mut self, hold: Duration) -> Self {
+        self.hold = hold;
+        self
+    }
+
+    /// Sets how long the screen has to read the same before a touch or release counts.
+    /// Defaults to 20ms.
+    pub const fn debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    fn column(x: i16) -> Action {
+        if x < Self::WIDTH / 3 {
+            Action::Previous
+        } else if x < Self::WIDTH * 2 / 3 {
+            Action::Confirm
+        } else {
+            Action::Next
+        }
+    }
+}
+
+impl InputSource for Touchscreen {
+    fn poll(&mut self) -> Option<Action> {
+        let point = (self.read)();
+        match self.level.update(point.is_some(), self.debounce) {
+            Some(true) => {
+                self.touch = point.map(|(x, _)| (x, Instant::now()));
+                self.handled = false;
+                None
+            }
+            Some(false) => {
+                let (x, _) = self.touch.take()?;
+                (!self.handled).then(|| Self::column(x))
+            }
+            None => {
+                let (_, start) = self.touch?;
+                if !self.handled && start.elapsed() >= self.hold {
+                    self.handled = true;
+                    return Some(Action::Back);
+                }
+                None
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        if (self.read)().is_some() {
+            self.level = Level::held();
+            self.touch = None;
+        }
+        self.handled = true;
+    }
+}
+
+/// A queue of actions which other code pushes, such as requests received over a radio.
+///
+/// Clones share the same queue, so one clone can be added as an input while another is kept to
+/// push to.
+#[derive(Debug, Clone, Default)]
+pub struct Events {
+    queue: Rc<RefCell<VecDeque<Action>>>,
+}
+
+impl Events {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an action to the end of the queue.
+    pub fn push(&self, action: Action) {
+        self.queue.borrow_mut().push_back(action);
+    }
+}
+
+impl InputSource for Events {
+    fn poll(&mut self) -> Option<Action> {
+        self.queue.borrow_mut().pop_front()
+    }
+}
@@ -0,0 +1,360 @@
+//! Configurable button mapping for selector navigation
+
+use alloc::{vec, vec::Vec};
+use core::time::Duration;
+
+use crate::platform::{ControllerState, Instant, JoystickState};
+
+/// A physical button on the V5 controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Button {
+    A,
+    B,
+    X,
+    Y,
+    Up,
+    Down,
+    Left,
+    Right,
+    L1,
+    L2,
+    R1,
+    R2,
+    Power,
+}
+
+impl Button {
+    /// Returns `true` if this button is held down in the provided controller state.
+    pub const fn is_pressed(self, state: &ControllerState) -> bool {
+        match self {
+            Button::A => state.button_a.is_pressed(),
+            Button::B => state.button_b.is_pressed(),
+            Button::X => state.button_x.is_pressed(),
+            Button::Y => state.button_y.is_pressed(),
+            Button::Up => state.button_up.is_pressed(),
+            Button::Down => state.button_down.is_pressed(),
+            Button::Left => state.button_left.is_pressed(),
+            Button::Right => state.button_right.is_pressed(),
+            Button::L1 => state.button_l1.is_pressed(),
+            Button::L2 => state.button_l2.is_pressed(),
+            Button::R1 => state.button_r1.is_pressed(),
+            Button::R2 => state.button_r2.is_pressed(),
+            Button::Power => state.button_power.is_pressed(),
+        }
+    }
+}
+
+/// A joystick on the V5 controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stick {
+    Left,
+    Right,
+}
+
+impl Stick {
+    const fn state(self, state: &ControllerState) -> JoystickState {
+        match self {
+            Stick::Left => state.left_stick,
+            Stick::Right => state.right_stick,
+        }
+    }
+}
+
+/// Navigation by flicking a joystick sideways.
+///
+/// Pushing the stick right past `threshold` moves to the next option and pushing it left moves
+/// to the previous one. The stick has to come back under half the threshold before it can
+/// trigger again, so it doesn't flicker around the edge.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StickNavigation {
+    pub stick: Stick,
+    /// Deflection on the x axis, from 0 to 1, that counts as a flick.
+    pub threshold: f64,
+}
+
+impl Default for StickNavigation {
+    fn default() -> Self {
+        Self {
+            stick: Stick::Left,
+            threshold: 0.7,
+        }
+    }
+}
+
+/// Auto-repeat settings for held navigation inputs.
+///
+/// After `delay`, a held [`Action::Next`] or [`Action::Previous`] repeats every `interval`. Each
+/// repeat multiplies the interval by `acceleration` until it reaches `min_interval`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Repeat {
+    pub delay: Duration,
+    pub interval: Duration,
+    pub acceleration: f64,
+    pub min_interval: Duration,
+}
+
+impl Default for Repeat {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_millis(400),
+            interval: Duration::from_millis(160),
+            acceleration: 0.85,
+            min_interval: Duration::from_millis(40),
+        }
+    }
+}
+
+/// A chord of one or more buttons, optionally held for some time, that triggers an [`Action`].
+///
+/// A binding with no buttons never triggers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    /// Buttons which must all be held at the same time.
+    pub chord: Vec<Button>,
+    /// How long the chord must be held before the binding triggers.
+    pub hold: Duration,
+}
+
+impl Binding {
+    /// Creates a binding triggered by pressing a single button.
+    pub fn new(button: Button) -> Self {
+        Self {
+            chord: vec![button],
+            hold: Duration::ZERO,
+        }
+    }
+
+    /// Creates a binding triggered by pressing all of the provided buttons together.
+    pub fn chord(buttons: impl IntoIterator<Item = Button>) -> Self {
+        Self {
+            chord: buttons.into_iter().collect(),
+            hold: Duration::ZERO,
+        }
+    }
+
+    /// Creates a binding which never triggers.
+    pub const fn unbound() -> Self {
+        Self {
+            chord: Vec::new(),
+            hold: Duration::ZERO,
+        }
+    }
+
+    /// Requires the chord to be held for `duration` before triggering.
+    pub const fn held(mut self, duration: Duration) -> Self {
+        self.hold = duration;
+        self
+    }
+
+    /// Returns `true` if the binding has at least one button.
+    pub fn is_bound(&self) -> bool {
+        !self.chord.is_empty()
+    }
+
+    /// Returns `true` if every button in the chord is held in the provided controller state.
+    pub fn is_pressed(&self, state: &ControllerState) -> bool {
+        self.is_bound() && self.chord.iter().all(|button| button.is_pressed(state))
+    }
+}
+
+/// A navigation action performed by the driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    /// Move to the next option.
+    Next,
+    /// Move to the previous option.
+    Previous,
+    /// Select the highlighted option.
+    Confirm,
+    /// Return to the previous screen.
+    Back,
+    /// Jump to the first option of the next group, such as the next category.
+    NextGroup,
+    /// Jump to the first option of the previous group.
+    PreviousGroup,
+    /// Leave the selector entirely.
+    Exit,
+    /// Open the selector again after leaving it.
+    Reopen,
+    /// Move straight to the option at an index, such as one tapped on a touchscreen.
+    Jump(usize),
+}
+
+impl Action {
+    /// Returns `true` if holding this action's input repeats it.
+    pub const fn repeats(self) -> bool {
+        matches!(self, Action::Next | Action::Previous)
+    }
+
+    /// Every action bound to buttons.
+    const ALL: [Action; 8] = [
+        Action::Next,
+        Action::Previous,
+        Action::Confirm,
+        Action::Back,
+        Action::NextGroup,
+        Action::PreviousGroup,
+        Action::Exit,
+        Action::Reopen,
+    ];
+}
+
+/// Maps controller buttons to the navigation [`Action`]s used by the selector.
+///
+/// The default keymap uses the d-pad or left stick to move, A to confirm, B to go back, L1 and R1
+/// to jump between groups and the power button to exit. Reopening the selector is unbound by
+/// default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    pub next: Binding,
+    pub previous: Binding,
+    pub confirm: Binding,
+    pub back: Binding,
+    pub next_group: Binding,
+    pub previous_group: Binding,
+    pub exit: Binding,
+    pub reopen: Binding,
+    /// Joystick which also moves between options, if any.
+    pub stick: Option<StickNavigation>,
+    /// Auto-repeat for held navigation inputs, if any.
+    pub repeat: Option<Repeat>,
+}
+
+impl Keymap {
+    /// Returns the binding for an action, or `None` for [`Action::Jump`], which has no button.
+    pub const fn binding(&self, action: Action) -> Option<&Binding> {
+        Some(match action {
+            Action::Next => &self.next,
+            Action::Previous => &self.previous,
+            Action::Confirm => &self.confirm,
+            Action::Back => &self.back,
+            Action::NextGroup => &self.next_group,
+            Action::PreviousGroup => &self.previous_group,
+            Action::Exit => &self.exit,
+            Action::Reopen => &self.reopen,
+            Action::Jump(_) => return None,
+        })
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            next: Binding::new(Button::Right),
+            previous: Binding::new(Button::Left),
+            confirm: Binding::new(Button::A),
+            back: Binding::new(Button::B),
+            next_group: Binding::new(Button::R1),
+            previous_group: Binding::new(Button::L1),
+            exit: Binding::new(Button::Power),
+            reopen: Binding::unbound(),
+            stick: Some(StickNavigation::default()),
+            repeat: Some(Repeat::default()),
+        }
+    }
+}
+
+/// Turns successive controller states into [`Action`]s according to a [`Keymap`].
+///
+/// Each binding triggers once per press, then repeats while held if the keymap allows it.
+/// Inputs already held when the reader is created or reset must be released first, so a press
+/// that closed one screen doesn't leak into the next. When several bindings trigger at once, the
+/// one with the largest chord wins.
+pub(crate) struct ActionReader {
+    keymap: Keymap,
+    held_since: [Option<Instant>; Action::ALL.len()],
+    triggered: [bool; Action::ALL.len()],
+    /// When the next repeat is due and the interval after it.
+    repeat: [Option<(Instant, Duration)>; Action::ALL.len()],
+    stick: Option<Action>,
+    primed: bool,
+}
+
+impl ActionReader {
+    pub fn new(keymap: Keymap) -> Self {
+        Self {
+            keymap,
+            held_since: [None; Action::ALL.len()],
+            triggered: [false; Action::ALL.len()],
+            repeat: [None; Action::ALL.len()],
+            stick: None,
+            primed: false,
+        }
+    }
+
+    /// Ignores inputs which are held right now until they are released.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.keymap.clone());
+    }
+
+    /// Feeds a new controller state into the reader, returning the action it triggers.
+    pub fn update(&mut self, state: &ControllerState) -> Option<Action> {
+        let now = Instant::now();
+        let mut result: Option<(Action, usize)> = None;
+        self.update_stick(state);
+
+        for (i, action) in Action::ALL.into_iter().enumerate() {
+            let Some(binding) = self.keymap.binding(action) else {
+                continue;
+            };
+            if !binding.is_pressed(state) && self.stick != Some(action) {
+                self.held_since[i] = None;
+                self.triggered[i] = false;
+                self.repeat[i] = None;
+                continue;
+            }
+            // A flicked stick has no chord, so any button binding triggered with it wins
+            let chord_len = if binding.is_pressed(state) {
+                binding.chord.len()
+            } else {
+                0
+            };
+
+            let held_since = *self.held_since[i].get_or_insert(now);
+            if !self.primed {
+                self.triggered[i] = true;
+            }
+            if self.triggered[i] {
+                match (self.repeat[i], self.keymap.repeat) {
+                    (Some((due, interval)), Some(repeat)) if now >= due => {
+                        let next_interval = interval
+                            .mul_f64(repeat.acceleration)
+                            .max(repeat.min_interval);
+                        self.repeat[i] = Some((now + interval, next_interval));
+                    }
+                    _ => continue,
+                }
+            } else if now.duration_since(held_since) < binding.hold {
+                continue;
+            } else {
+                self.triggered[i] = true;
+                if let Some(repeat) = self.keymap.repeat.filter(|_| action.repeats()) {
+                    self.repeat[i] = Some((now + repeat.delay, repeat.interval));
+                }
+            }
+
+            if result.is_none_or(|(_, len)| chord_len > len) {
+                result = Some((action, chord_len));
+            }
+        }
+
+        self.primed = true;
+        result.map(|(action, _)| action)
+    }
+
+    fn update_stick(&mut self, state: &ControllerState) {
+        let Some(navigation) = self.keymap.stick else {
+            self.stick = None;
+            return;
+        };
+        let x = navigation.stick.state(state).x();
+
+        if x >= navigation.threshold {
+            self.stick = Some(Action::Next);
+        } else if x <= -navigation.threshold {
+            self.stick = Some(Action::Previous);
+        } else if x.abs() < navigation.threshold / 2.0 {
+            self.stick = None;
+        }
+    }
+}
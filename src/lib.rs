@@ -4,35 +4,68 @@
 #![feature(never_type)]
 
 extern crate alloc;
+#[cfg(not(target_vendor = "vex"))]
+extern crate std;
 
 use alloc::{boxed::Box, format, rc::Rc, vec, vec::Vec};
-use core::{cell::RefCell, fmt::Display, time::Duration};
+use core::{cell::RefCell, fmt::Display, pin::pin, time::Duration};
 
+#[cfg(target_vendor = "vex")]
 use autons::Selector;
-use display::{horizontal_picker, simple_dialog};
-use vexide::{
-    devices::controller::*,
-    task::{self, Task},
-    time::sleep,
-};
+use builder::SelectorOptions;
+use dial::DialInput;
+use futures::future::{pending, select, Either};
+use input::Events;
+use platform::{competition_connected, sleep, spawn, Instant, Task};
+use screen::{Flow, Insertion};
+use screens::{DoneScreen, PickedRoute};
+use serial::SerialLink;
+use view::View;
+use widgets::ControllerUi;
 
-use crate::alloc::string::ToString;
-
-mod display;
+mod builder;
+mod check;
+pub mod diagnostics;
+pub mod dial;
+pub mod display;
+#[cfg(not(target_vendor = "vex"))]
+pub mod emulator;
+#[cfg(feature = "graphics")]
+pub mod graphics;
+pub mod input;
+mod keymap;
+mod options;
+mod platform;
 mod route;
+pub mod screen;
+mod screens;
+pub mod serial;
+mod shared;
+#[cfg(not(target_vendor = "vex"))]
+pub mod simulator;
+pub mod view;
+pub mod widgets;
 
+#[cfg(feature = "derive")]
+pub use autons_controller_macros::Routes;
+pub use builder::ControllerSelectBuilder;
+pub use check::Check;
+pub use keymap::*;
+pub use options::*;
 pub use route::*;
+pub use screen::{Screen, Transition};
+pub use shared::SharedController;
+pub use widgets::Timing;
 
-struct SelectorState<R: 'static, C: Clone + Display + PartialEq, const N: usize> {
-    routes: [Route<R, C>; N],
+pub(crate) struct SelectorState<R: 'static, C: Clone + Display + PartialEq> {
+    routes: Vec<Route<R, C>>,
     selection: Option<usize>,
-}
-
-enum SelectorStateType<R: 'static, C: Clone + Display + PartialEq> {
-    Category,
-    Route(C),
-    Confirm(Route<R, C>),
-    Done(Route<R, C>),
+    /// Routes to run in order instead of the selection, with their option values
+    playlist: Vec<(usize, RouteContext)>,
+    start_delay: Duration,
+    context: RouteContext,
+    /// Number of times a [dial](dial) has picked another route
+    dial_turns: u32,
 }
 
 pub struct ControllerSelect<
@@ -40,16 +73,73 @@ pub struct ControllerSelect<
     C: Clone + Display + PartialEq + PartialOrd + 'static,
     const N: usize,
 > {
-    state: Rc<RefCell<SelectorState<R, C, N>>>,
+    state: Rc<RefCell<SelectorState<R, C>>>,
+    ui: Rc<ControllerUi>,
     _task: Task<()>,
+    _serial_task: Option<Task<()>>,
+    _dial_task: Option<Task<()>>,
+}
+
+impl<R, C: Clone + Display + PartialEq + 'static> SelectorState<R, C> {
+    /// Selects the route at `index` with its options at their defaults.
+    fn select_index(&mut self, index: usize) {
+        self.selection = Some(index);
+        self.playlist.clear();
+        self.context = RouteContext::defaults(&self.routes[index].options);
+    }
+
+    /// Selects the route at `index` along with its context.
+    fn select_route(&mut self, index: usize, context: RouteContext) {
+        self.selection = Some(index);
+        self.playlist.clear();
+        self.context = context;
+    }
+
+    /// Stores the routes at the provided indices as the playlist to run.
+    fn select_playlist(&mut self, playlist: Vec<(usize, RouteContext)>) {
+        self.selection = None;
+        self.playlist = playlist;
+    }
 }
 
 impl<R, C: Clone + Display + PartialEq + Ord + 'static, const N: usize> ControllerSelect<R, C, N> {
-    /// Creates a new selector from a [`Display`] peripheral and array of routes.
+    /// Creates a new selector from a [`SharedController`] and array of routes.
     pub fn new(
-        controller: Rc<RefCell<Controller>>,
+        controller: impl SharedController + 'static,
+        is_selecting: Rc<RefCell<bool>>,
+        routes: [Route<R, C>; N],
+    ) -> Self {
+        Self::builder(controller, is_selecting, routes).build()
+    }
+
+    /// Creates a new selector which navigates using the buttons in the provided [`Keymap`].
+    pub fn with_keymap(
+        controller: impl SharedController + 'static,
         is_selecting: Rc<RefCell<bool>>,
         routes: [Route<R, C>; N],
+        keymap: Keymap,
+    ) -> Self {
+        Self::builder(controller, is_selecting, routes)
+            .keymap(keymap)
+            .build()
+    }
+
+    /// Starts configuring a selector with a [`ControllerSelectBuilder`].
+    pub fn builder(
+        controller: impl SharedController + 'static,
+        is_selecting: Rc<RefCell<bool>>,
+        routes: [Route<R, C>; N],
+    ) -> ControllerSelectBuilder<R, C, N> {
+        ControllerSelectBuilder::new(controller, is_selecting, routes)
+    }
+
+    pub(crate) fn spawn(
+        ui: ControllerUi,
+        routes: [Route<R, C>; N],
+        options: SelectorOptions,
+        insertions: Vec<Insertion<R, C>>,
+        serial: Option<Box<dyn SerialLink>>,
+        dial: Option<Box<dyn DialInput>>,
     ) -> Self {
         const {
             assert!(N > 0, "ControllerSelect requires at least one route.");
@@ -63,147 +153,250 @@ impl<R, C: Clone + Display + PartialEq + Ord + 'static, const N: usize> Controll
         categories.dedup();
 
         let state = Rc::new(RefCell::new(SelectorState {
-            routes,
+            routes: routes.into(),
             selection: None,
+            playlist: Vec::new(),
+            start_delay: Duration::ZERO,
+            context: RouteContext::default(),
+            dial_turns: 0,
         }));
+        // Requests sent over serial to navigate the menu
+        let events = Events::new();
+        let ui = Rc::new(if serial.is_some() {
+            ui.with_input(events.clone())
+        } else {
+            ui
+        });
+        let serial_task = serial.map(|link| {
+            spawn(serial::serve(
+                link,
+                state.clone(),
+                events,
+                options.timing.poll,
+            ))
+        });
+        let has_dial = dial.is_some();
+        let dial_task = dial.map(|input| {
+            spawn(dial::follow(
+                input,
+                state.clone(),
+                options.dial_debounce,
+                options.timing.poll,
+            ))
+        });
+        let mut flow = Flow::new(ui.clone(), state.clone(), options, categories, insertions);
 
         Self {
             state: state.clone(),
-            _task: task::spawn(async move {
-                let mut state_type = SelectorStateType::Category;
+            ui: ui.clone(),
+            _task: spawn(async move {
+                // Screens the driver has passed through. The first screen is never closed.
+                let mut stack = vec![flow.first_screen()];
                 loop {
                     // If we're connected to a comp control system, we should
                     // exit since picker should happen before we plug in
-                    if vexide::competition::is_connected() || !*is_selecting.borrow() {
-                        is_selecting.replace(false);
+                    if competition_connected() || !ui.is_active() {
+                        ui.set_active(false);
                         sleep(Duration::from_millis(400)).await;
-                        _ = controller.borrow_mut().screen.try_clear_screen();
-                        break;
-                    }
-                    match state_type {
-                        SelectorStateType::Category => {
-                            match horizontal_picker(
-                                controller.clone(),
-                                is_selecting.clone(),
-                                "- Pick category -",
-                                categories
-                                    .clone()
-                                    .into_iter()
-                                    .map(|c| c.to_string())
-                                    .collect::<Vec<_>>(),
-                            )
-                            .await
-                            {
-                                Some(selected) => {
-                                    state_type =
-                                        SelectorStateType::Route(categories[selected].clone());
-                                }
-                                None => state_type = SelectorStateType::Category,
-                            }
+                        ui.clear().await;
+                        if !ui.keymap().reopen.is_bound() {
+                            break;
                         }
-                        SelectorStateType::Route(category) => {
-                            let route_picker_header = format!("- {} -", category);
-                            let routes = state.borrow().routes.clone();
-                            let filtered_routes = routes
-                                .clone()
-                                .into_iter()
-                                .filter(|route| route.category == category)
-                                .collect::<Vec<_>>();
-                            match horizontal_picker(
-                                controller.clone(),
-                                is_selecting.clone(),
-                                &route_picker_header,
-                                filtered_routes
-                                    .iter()
-                                    .map(|route| route.name.to_string())
-                                    .collect::<Vec<_>>(),
-                            )
-                            .await
-                            {
-                                Some(selected) => {
-                                    let selected = &filtered_routes[selected];
-                                    state_type = SelectorStateType::Confirm(selected.clone());
-                                }
-                                None => state_type = SelectorStateType::Category,
+
+                        // Wait for the driver to ask for the selector again
+                        ui.reset_inputs();
+                        loop {
+                            if competition_connected() {
+                                return;
                             }
-                        }
-                        SelectorStateType::Confirm(route) => {
-                            let header = format!("Pick in {}:", route.category);
-                            match horizontal_picker(
-                                controller.clone(),
-                                is_selecting.clone(),
-                                &header,
-                                vec!["Cancel".to_string(), format!("Confirm {}", route.name)],
-                            )
-                            .await
-                            {
-                                Some(selected) => {
-                                    if selected == 0 {
-                                        state_type = SelectorStateType::Route(route.category);
-                                    } else {
-                                        let mut state = state.borrow_mut();
-                                        state.selection = Some(
-                                            state
-                                                .routes
-                                                .iter()
-                                                .position(|r| {
-                                                    r.name == route.name
-                                                        && r.category == route.category
-                                                })
-                                                .unwrap(),
-                                        );
-                                        state_type = SelectorStateType::Done(route.clone());
-                                    }
-                                }
-                                None => state_type = SelectorStateType::Route(route.category),
+                            if ui.next_action() == Some(Action::Reopen) {
+                                break;
                             }
+                            sleep(ui.timing().poll).await;
+                        }
+                        ui.set_active(true);
+                        stack.truncate(1);
+                        continue;
+                    }
+
+                    let mut screen = stack.pop().unwrap();
+                    let turns = state.borrow().dial_turns;
+                    let dial_turned = pin!(async {
+                        if has_dial {
+                            dial::turned(&state, turns, ui.timing().poll).await;
+                        } else {
+                            pending::<()>().await;
                         }
-                        SelectorStateType::Done(ref route) => {
-                            let description = format!("{} / {}", route.category, route.name);
-                            is_selecting.replace(true);
-                            simple_dialog(
-                                controller.clone(),
-                                is_selecting.clone(),
-                                "- Route selected -",
-                                &description,
-                                Some("Good luck!"),
-                            )
-                            .await;
+                    });
+                    let transition = match select(screen.show(&mut flow), dial_turned).await {
+                        Either::Left((transition, _)) => Some(transition),
+                        Either::Right(_) => None,
+                    };
+                    let Some(transition) = transition else {
+                        // The dial picked another route, so show it in place of whatever was open
+                        if stack.is_empty() {
+                            stack.push(screen);
                         }
+                        stack.truncate(1);
+                        let route = {
+                            let state = state.borrow();
+                            state.selection.map(|index| PickedRoute {
+                                index,
+                                route: state.routes[index].clone(),
+                            })
+                        };
+                        ui.clear().await;
+                        stack.push(Box::new(DoneScreen::new(route)));
+                        continue;
+                    };
+                    match transition {
+                        Transition::Push(next) => {
+                            stack.push(screen);
+                            stack.push(next);
+                        }
+                        Transition::Replace(next) => stack.push(next),
+                        // Backing out of the first screen leaves it open
+                        Transition::Back if stack.is_empty() => stack.push(screen),
+                        Transition::Back => {}
+                        Transition::Home if stack.is_empty() => stack.push(screen),
+                        Transition::Home => stack.truncate(1),
+                        Transition::Next | Transition::Stay => stack.push(screen),
                     }
                 }
             }),
+            _serial_task: serial_task,
+            _dial_task: dial_task,
         }
     }
 
     /// Programmatically selects an autonomous route by index.
     pub fn select(&mut self, index: usize) {
         assert!(index < N, "Invalid route selection index.");
-        let mut state = self.state.borrow_mut();
-        state.selection = Some(index);
+        self.state.borrow_mut().select_index(index);
+    }
+
+    /// Programmatically sets how long to wait before the selected route starts.
+    pub fn set_start_delay(&mut self, delay: Duration) {
+        self.state.borrow_mut().start_delay = delay;
+    }
+}
+
+/// Shows a countdown on the controller until `delay` has passed.
+async fn count_down(ui: &ControllerUi, delay: Duration) {
+    let start = Instant::now();
+    ui.clear().await;
+    while let Some(remaining) = delay.checked_sub(start.elapsed()) {
+        if remaining.is_zero() {
+            break;
+        }
+        // Leave the controller ready to show "Go!" the moment the delay is up
+        if remaining > Timing::MIN_REDRAW {
+            ui.show_view(View::message(format!(
+                "Starting in {:.1}s",
+                remaining.as_secs_f64()
+            )));
+        }
+        sleep(remaining.min(ui.timing().poll)).await;
+    }
+    // Don't hold up the route waiting for the controller
+    ui.show_view(View::message("Go!"));
+}
+
+impl<R, C: Clone + Display + PartialEq + PartialOrd + 'static, const N: usize>
+    ControllerSelect<R, C, N>
+{
+    /// Returns the routes that will run, with their option values.
+    ///
+    /// This is the selected route, or the routes of the playlist in order. It is empty until
+    /// something has been selected.
+    pub fn selection(&self) -> Vec<(Route<R, C>, RouteContext)> {
+        let state = self.state.borrow();
+        match state.selection {
+            Some(selection) => vec![(state.routes[selection].clone(), state.context.clone())],
+            None => state
+                .playlist
+                .iter()
+                .map(|(index, context)| (state.routes[*index].clone(), context.clone()))
+                .collect(),
+        }
+    }
+
+    /// Returns how long to wait before the selection starts.
+    pub fn start_delay(&self) -> Duration {
+        self.state.borrow().start_delay
+    }
+
+    /// Counts down the start delay and runs the selection, which is what the robot does when
+    /// autonomous starts.
+    ///
+    /// A playlist stops at the first route that fails or times out.
+    pub async fn run_selection(&self, robot: &mut R) {
+        let routes = self.selection();
+        if routes.is_empty() {
+            return;
+        }
+        let start_delay = self.start_delay();
+        if !start_delay.is_zero() {
+            count_down(&self.ui, start_delay).await;
+        }
+
+        let is_playlist = routes.len() > 1;
+        for (i, (route, context)) in routes.iter().enumerate() {
+            if is_playlist {
+                let progress = format!("{}/{} {}", i + 1, routes.len(), route.name);
+                self.ui.show_view(View::message(progress));
+            }
+            if !run_route(robot, route, context.clone()).await && is_playlist {
+                let stopped = format!("Stopped at {}", route.name);
+                self.ui.draw(View::message(stopped)).await;
+                return;
+            }
+        }
     }
 }
 
+#[cfg(target_vendor = "vex")]
 impl<R, C: Clone + Display + PartialEq + PartialOrd + 'static, const N: usize> Selector<R>
     for ControllerSelect<R, C, N>
 {
     async fn run(&self, robot: &mut R) {
-        {
-            let state = self.state.borrow();
-            if let Some(selection) = state.selection {
-                (state.routes[selection].callback)(robot)
-            } else {
-                Box::pin(async {})
+        self.run_selection(robot).await;
+    }
+}
+
+/// Runs a route until it returns or times out, returning `false` if it failed or timed out.
+async fn run_route<R, C: Clone + Display + PartialEq>(
+    robot: &mut R,
+    route: &Route<R, C>,
+    context: RouteContext,
+) -> bool {
+    context.clear_failure();
+    let future = route.callback.call(robot, context.clone());
+    match route.timeout {
+        Some(timeout) => {
+            let timer = pin!(sleep(timeout));
+            if let Either::Right(_) = select(future, timer).await {
+                return false;
             }
         }
-        .await;
+        None => future.await,
     }
+    !context.has_failed()
 }
 
 /// This module is intended to be glob imported.
 pub mod prelude {
+    #[cfg(feature = "derive")]
+    pub use super::Routes;
     pub use super::{
+        builder::ControllerSelectBuilder,
+        check::Check,
+        diagnostics::ExpectedDevice,
+        keymap::{Binding, Button, Keymap, Repeat, Stick, StickNavigation},
+        options::{RouteContext, RouteOption},
         route::{route, Route},
+        screen::{Ask, Flow, FlowPoint, Screen, ScreenFuture, Transition},
+        shared::SharedController,
         ControllerSelect,
     };
 }
@@ -0,0 +1,206 @@
+//! Typed per-route options chosen after the route
+
+use alloc::{
+    format,
+    rc::Rc,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::cell::Cell;
+
+/// The type, bounds and default of a [`RouteOption`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionKind {
+    /// An on or off switch.
+    Bool { default: bool },
+    /// One of several labelled choices, identified by index.
+    Choice {
+        choices: Vec<String>,
+        default: usize,
+    },
+    /// A whole number between two bounds.
+    Integer { min: i32, max: i32, default: i32 },
+}
+
+/// A value chosen for a [`RouteOption`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionValue {
+    Bool(bool),
+    Choice(usize),
+    Integer(i32),
+}
+
+/// A setting a route asks for after it is picked, such as "rush middle" or a number of rings.
+///
+/// Options are added to a route with [`Route::option`], and their values are passed to the
+/// route through a [`RouteContext`].
+///
+/// [`Route::option`]: crate::Route::option
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteOption {
+    pub name: String,
+    pub kind: OptionKind,
+}
+
+impl RouteOption {
+    /// Creates an on or off option.
+    pub fn toggle(name: impl Into<String>, default: bool) -> Self {
+        Self {
+            name: name.into(),
+            kind: OptionKind::Bool { default },
+        }
+    }
+
+    /// Creates an option with several labelled choices, starting on `default`.
+    pub fn choice<S: Into<String>>(
+        name: impl Into<String>,
+        choices: impl IntoIterator<Item = S>,
+        default: usize,
+    ) -> Self {
+        let choices = choices.into_iter().map(Into::into).collect::<Vec<_>>();
+        assert!(default < choices.len(), "Default choice is out of range.");
+        Self {
+            name: name.into(),
+            kind: OptionKind::Choice { choices, default },
+        }
+    }
+
+    /// Creates a whole number option between `min` and `max` inclusive.
+    pub fn integer(name: impl Into<String>, min: i32, max: i32, default: i32) -> Self {
+        assert!(
+            (min..=max).contains(&default),
+            "Default value is out of range."
+        );
+        Self {
+            name: name.into(),
+            kind: OptionKind::Integer { min, max, default },
+        }
+    }
+
+    /// Returns the value used when the driver doesn't change the option.
+    pub fn default_value(&self) -> OptionValue {
+        match self.kind {
+            OptionKind::Bool { default } => OptionValue::Bool(default),
+            OptionKind::Choice { default, .. } => OptionValue::Choice(default),
+            OptionKind::Integer { default, .. } => OptionValue::Integer(default),
+        }
+    }
+
+    /// Formats a value of this option for the controller.
+    pub fn format_value(&self, value: OptionValue) -> String {
+        match (&self.kind, value) {
+            (OptionKind::Choice { choices, .. }, OptionValue::Choice(index)) => choices
+                .get(index)
+                .cloned()
+                .unwrap_or_else(|| index.to_string()),
+            (_, OptionValue::Bool(true)) => "on".to_string(),
+            (_, OptionValue::Bool(false)) => "off".to_string(),
+            (_, OptionValue::Choice(index)) => index.to_string(),
+            (_, OptionValue::Integer(value)) => value.to_string(),
+        }
+    }
+}
+
+/// The option values chosen for a route, passed to routes created with
+/// [`Route::with_context`] or [`Route::from_closure`].
+///
+/// [`Route::with_context`]: crate::Route::with_context
+/// [`Route::from_closure`]: crate::Route::from_closure
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RouteContext {
+    values: Vec<(String, OptionValue)>,
+    failed: Rc<Cell<bool>>,
+}
+
+impl RouteContext {
+    /// Creates a context with every option at its default value.
+    pub fn defaults(options: &[RouteOption]) -> Self {
+        Self {
+            values: options
+                .iter()
+                .map(|option| (option.name.clone(), option.default_value()))
+                .collect(),
+            failed: Rc::default(),
+        }
+    }
+
+    pub(crate) fn from_values(options: &[RouteOption], values: &[OptionValue]) -> Self {
+        Self {
+            values: options
+                .iter()
+                .zip(values)
+                .map(|(option, value)| (option.name.clone(), *value))
+                .collect(),
+            failed: Rc::default(),
+        }
+    }
+
+    /// Adds answers from custom screens, unless a route option has the same name.
+    pub(crate) fn with_answers(mut self, answers: &[(String, OptionValue)]) -> Self {
+        for (name, value) in answers {
+            if self.get(name).is_none() {
+                self.values.push((name.clone(), *value));
+            }
+        }
+        self
+    }
+
+    /// Returns the value of an option, if the route has an option with that name.
+    pub fn get(&self, name: &str) -> Option<OptionValue> {
+        self.values
+            .iter()
+            .find(|(option, _)| option == name)
+            .map(|(_, value)| *value)
+    }
+
+    /// Returns the value of an on or off option.
+    pub fn bool(&self, name: &str) -> Option<bool> {
+        match self.get(name)? {
+            OptionValue::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the index of the chosen choice of an option.
+    pub fn choice(&self, name: &str) -> Option<usize> {
+        match self.get(name)? {
+            OptionValue::Choice(index) => Some(index),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a whole number option.
+    pub fn integer(&self, name: &str) -> Option<i32> {
+        match self.get(name)? {
+            OptionValue::Integer(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Marks the route as failed, so that the rest of a playlist is skipped once it returns.
+    pub fn fail(&self) {
+        self.failed.set(true);
+    }
+
+    /// Returns `true` if the route has called [`fail`](Self::fail).
+    pub fn has_failed(&self) -> bool {
+        self.failed.get()
+    }
+
+    pub(crate) fn clear_failure(&self) {
+        self.failed.set(false);
+    }
+
+    /// Returns `name=value` for every option not at its default, separated by spaces.
+    pub fn summary(&self, options: &[RouteOption]) -> String {
+        options
+            .iter()
+            .filter_map(|option| {
+                let value = self.get(&option.name)?;
+                (value != option.default_value())
+                    .then(|| format!("{}={}", option.name, option.format_value(value)))
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
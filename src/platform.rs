@@ -0,0 +1,50 @@
+//! The controller, clock, tasks and brain, from vexide on the V5 and from the emulator
+//! everywhere else
+
+#[cfg(target_vendor = "vex")]
+pub use vexide::{
+    competition::is_connected as competition_connected,
+    devices::{
+        battery::capacity as brain_battery,
+        controller::{Controller, ControllerState, JoystickState},
+        smart::SmartDeviceType,
+    },
+    task::{spawn, Task},
+    time::{sleep, Instant},
+};
+
+#[cfg(not(target_vendor = "vex"))]
+pub(crate) use crate::emulator::{
+    brain_battery, competition_connected, motor_overheating, smart_port_type,
+};
+#[cfg(not(target_vendor = "vex"))]
+pub use crate::emulator::{
+    sleep, spawn, Controller, ControllerState, Instant, JoystickState, SmartDeviceType, Task,
+};
+
+/// Returns the type of device plugged into the smart port at `index`, if there is one.
+#[cfg(target_vendor = "vex")]
+pub(crate) fn smart_port_type(index: usize) -> Option<SmartDeviceType> {
+    use vex_sdk::{vexDeviceGetStatus, V5_DeviceType, V5_MAX_DEVICE_PORTS};
+
+    let mut device_types = [V5_DeviceType::kDeviceTypeNoSensor; V5_MAX_DEVICE_PORTS];
+    unsafe {
+        vexDeviceGetStatus(device_types.as_mut_ptr());
+    }
+    match device_types[index] {
+        V5_DeviceType::kDeviceTypeNoSensor => None,
+        raw_type => Some(raw_type.into()),
+    }
+}
+
+/// Returns the temperature of the motor at `index` if it has reached its temperature limit.
+#[cfg(target_vendor = "vex")]
+pub(crate) fn motor_overheating(index: usize) -> Option<f64> {
+    use vex_sdk::{
+        vexDeviceGetByIndex, vexDeviceMotorOverTempFlagGet, vexDeviceMotorTemperatureGet,
+    };
+
+    let handle = unsafe { vexDeviceGetByIndex(index as u32) };
+    unsafe { vexDeviceMotorOverTempFlagGet(handle) }
+        .then(|| unsafe { vexDeviceMotorTemperatureGet(handle) })
+}
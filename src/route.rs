@@ -1,7 +1,82 @@
-use alloc::boxed::Box;
-use core::{fmt::Display, future::Future, pin::Pin};
+use alloc::{borrow::Cow, boxed::Box, rc::Rc, vec::Vec};
+use core::{
+    fmt::{self, Display},
+    future::Future,
+    pin::Pin,
+    ptr,
+    time::Duration,
+};
+
+use crate::{diagnostics::ExpectedDevice, Check, RouteContext, RouteOption};
 
 type RouteFn<Shared> = for<'s> fn(&'s mut Shared) -> Pin<Box<dyn Future<Output = ()> + 's>>;
+type RouteContextFn<Shared> =
+    for<'s> fn(&'s mut Shared, RouteContext) -> Pin<Box<dyn Future<Output = ()> + 's>>;
+type RouteClosure<Shared> =
+    dyn for<'s> Fn(&'s mut Shared, RouteContext) -> Pin<Box<dyn Future<Output = ()> + 's>>;
+
+/// The function a [`Route`] runs.
+pub enum RouteCallback<R> {
+    /// A route which only takes the robot.
+    Plain(RouteFn<R>),
+    /// A route which also takes the values of its options.
+    WithContext(RouteContextFn<R>),
+    /// A closure which may capture data, such as a path loaded at runtime, and also takes the
+    /// values of its options.
+    Closure(Rc<RouteClosure<R>>),
+}
+
+impl<R> Clone for RouteCallback<R> {
+    fn clone(&self) -> Self {
+        match self {
+            RouteCallback::Plain(callback) => RouteCallback::Plain(*callback),
+            RouteCallback::WithContext(callback) => RouteCallback::WithContext(*callback),
+            RouteCallback::Closure(callback) => RouteCallback::Closure(callback.clone()),
+        }
+    }
+}
+
+impl<R> fmt::Debug for RouteCallback<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RouteCallback::Plain(callback) => f.debug_tuple("Plain").field(callback).finish(),
+            RouteCallback::WithContext(callback) => {
+                f.debug_tuple("WithContext").field(callback).finish()
+            }
+            RouteCallback::Closure(_) => f.debug_tuple("Closure").finish_non_exhaustive(),
+        }
+    }
+}
+
+impl<R> PartialEq for RouteCallback<R> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (RouteCallback::Plain(a), RouteCallback::Plain(b)) => ptr::fn_addr_eq(*a, *b),
+            (RouteCallback::WithContext(a), RouteCallback::WithContext(b)) => {
+                ptr::fn_addr_eq(*a, *b)
+            }
+            (RouteCallback::Closure(a), RouteCallback::Closure(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+impl<R> Eq for RouteCallback<R> {}
+
+impl<R> RouteCallback<R> {
+    /// Starts the route on a robot.
+    pub fn call<'s>(
+        &self,
+        robot: &'s mut R,
+        context: RouteContext,
+    ) -> Pin<Box<dyn Future<Output = ()> + 's>> {
+        match self {
+            RouteCallback::Plain(callback) => callback(robot),
+            RouteCallback::WithContext(callback) => callback(robot, context),
+            RouteCallback::Closure(callback) => callback(robot, context),
+        }
+    }
+}
 
 /// Route entry for [`SimpleSelect`].
 ///
@@ -15,28 +90,111 @@ type RouteFn<Shared> = for<'s> fn(&'s mut Shared) -> Pin<Box<dyn Future<Output =
 #[derive(Debug, Eq, PartialEq)]
 pub struct Route<R, C: Display + Clone + PartialEq> {
     pub category: C,
-    pub name: &'static str,
-    pub callback: RouteFn<R>,
+    /// Name shown in the selector, which may be computed at runtime.
+    pub name: Cow<'static, str>,
+    pub callback: RouteCallback<R>,
+    /// Settings the driver picks after this route, passed to it in a [`RouteContext`].
+    pub options: Vec<RouteOption>,
+    /// Pre-flight checks specific to this route.
+    pub checks: Vec<Check>,
+    /// Smart devices this route needs, in addition to those of the whole robot.
+    pub devices: Vec<ExpectedDevice>,
+    /// How long the route may run before it is stopped.
+    pub timeout: Option<Duration>,
 }
 
 impl<R, C: Display + Clone + PartialEq> Clone for Route<R, C> {
     fn clone(&self) -> Self {
         Self {
-            name: self.name,
+            name: self.name.clone(),
             category: self.category.clone(),
-            callback: self.callback,
+            callback: self.callback.clone(),
+            options: self.options.clone(),
+            checks: self.checks.clone(),
+            devices: self.devices.clone(),
+            timeout: self.timeout,
         }
     }
 }
 
 impl<R, C: Display + Clone + PartialEq> Route<R, C> {
-    pub fn new(category: C, name: &'static str, callback: RouteFn<R>) -> Self {
+    pub fn new(category: C, name: impl Into<Cow<'static, str>>, callback: RouteFn<R>) -> Self {
+        Self::from_callback(category, name, RouteCallback::Plain(callback))
+    }
+
+    /// Creates a route whose function also takes the values of its [options](Self::option).
+    pub fn with_context(
+        category: C,
+        name: impl Into<Cow<'static, str>>,
+        callback: RouteContextFn<R>,
+    ) -> Self {
+        Self::from_callback(category, name, RouteCallback::WithContext(callback))
+    }
+
+    /// Creates a route from a closure, which unlike [`Route::new`] may capture data.
+    ///
+    /// This lets one function be registered as several routes, such as a path follower given a
+    /// different path for each. Like [`Route::with_context`], the closure also takes the values
+    /// of the route's options, and can [fail](RouteContext::fail) to stop a playlist:
+    ///
+    /// ```ignore
+    /// let routes = paths.map(|(name, path)| {
+    ///     route!(Category::Skills, name, |robot, context| {
+    ///         robot.follow_path(path.clone(), context)
+    ///     })
+    /// });
+    /// ```
+    pub fn from_closure<F>(category: C, name: impl Into<Cow<'static, str>>, callback: F) -> Self
+    where
+        F: for<'s> Fn(&'s mut R, RouteContext) -> Pin<Box<dyn Future<Output = ()> + 's>> + 'static,
+    {
+        Self::from_callback(category, name, RouteCallback::Closure(Rc::new(callback)))
+    }
+
+    fn from_callback(
+        category: C,
+        name: impl Into<Cow<'static, str>>,
+        callback: RouteCallback<R>,
+    ) -> Self {
         Self {
             category,
-            name,
+            name: name.into(),
             callback,
+            options: Vec::new(),
+            checks: Vec::new(),
+            devices: Vec::new(),
+            timeout: None,
         }
     }
+
+    /// Adds a setting the driver picks after this route.
+    ///
+    /// Options are asked in the order they're added. Routes created with
+    /// [`Route::with_context`] receive the chosen values.
+    pub fn option(mut self, option: RouteOption) -> Self {
+        self.options.push(option);
+        self
+    }
+
+    /// Adds a pre-flight check which must pass, or be overridden, before this route is confirmed.
+    pub fn check(mut self, check: Check) -> Self {
+        self.checks.push(check);
+        self
+    }
+
+    /// Stops the route if it is still running after `timeout`.
+    ///
+    /// In a playlist, the routes after a timed out route are skipped.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Declares a smart device this route needs, which is scanned before it is confirmed.
+    pub fn expect_device(mut self, device: ExpectedDevice) -> Self {
+        self.devices.push(device);
+        self
+    }
 }
 
 /// Concisely creates an instance of a [`SimpleSelectRoute`].
@@ -64,8 +222,20 @@ impl<R, C: Display + Clone + PartialEq> Route<R, C> {
 ///     route!(Category::Category2, "Route 2", Robot::route_2),
 /// ];
 /// ```
+///
+/// A closure taking the robot and the route's [`RouteContext`] creates the route with
+/// [`Route::from_closure`] instead. Its body is the future the route runs:
+///
+/// ```ignore
+/// route!(Category::Category1, name, |robot, context| robot.follow_path(path.clone(), context))
+/// ```
 #[macro_export]
 macro_rules! route {
+    ($category:expr, $name:expr, $(move)? |$robot:pat_param, $context:pat_param| $body:expr) => {{
+        ::autons_controller::Route::from_closure($category, $name, move |$robot, $context| {
+            ::alloc::boxed::Box::pin($body)
+        })
+    }};
     ($category:expr, $name:expr, $func:path) => {{
         ::autons_controller::Route::new($category, $name, |robot| {
             ::alloc::boxed::Box::pin($func(robot))
@@ -0,0 +1,453 @@
+//! Screens making up the selection flow
+//!
+//! Every step of the selector, from the category list to the done dialog, is a [`Screen`]. The
+//! selector keeps the screens the driver has passed through on a stack, so backing out of one
+//! returns to the screen before it exactly as it was left.
+//!
+//! Custom screens are inserted at a [`FlowPoint`] with [`ControllerSelectBuilder::screen`] or,
+//! only when earlier answers call for them, [`ControllerSelectBuilder::screen_if`]. They store
+//! their results with [`Flow::set_answer`], which routes read from their [`RouteContext`].
+//!
+//! [`ControllerSelectBuilder::screen`]: crate::ControllerSelectBuilder::screen
+//! [`ControllerSelectBuilder::screen_if`]: crate::ControllerSelectBuilder::screen_if
+
+use alloc::{
+    boxed::Box,
+    format,
+    rc::Rc,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{cell::RefCell, fmt::Display, future::Future, pin::Pin};
+
+use crate::{
+    builder::SelectorOptions,
+    screens::{
+        CategoryScreen, DelayScreen, DoneScreen, ListScreen, PickedRoute, PlaylistScreen,
+        RouteScreen,
+    },
+    widgets::{ControllerUi, Picker, Spinner, Toggle},
+    OptionKind, OptionValue, Route, RouteContext, RouteOption, SelectorState,
+};
+
+/// The future returned by [`Screen::show`].
+pub type ScreenFuture<'a, R, C> = Pin<Box<dyn Future<Output = Transition<R, C>> + 'a>>;
+
+/// A step of the selection flow.
+///
+/// A screen usually shows a widget from [`widgets`](crate::widgets) on [`Flow::ui`] and turns
+/// the driver's choice into a [`Transition`].
+///
+/// ```ignore
+/// struct PartnerGoal;
+///
+/// impl Screen<Robot, Category> for PartnerGoal {
+///     fn show<'a>(&'a mut self, flow: &'a mut Flow<Robot, Category>) -> ScreenFuture<'a, Robot, Category> {
+///         Box::pin(async move {
+///             let goals = vec!["Left".to_string(), "Right".to_string()];
+///             match Picker::new("Partner goal", goals).show(flow.ui()).await {
+///                 Some(goal) => {
+///                     flow.set_answer("Partner goal", OptionValue::Choice(goal));
+///                     Transition::Next
+///                 }
+///                 None => Transition::Back,
+///             }
+///         })
+///     }
+/// }
+/// ```
+pub trait Screen<R: 'static, C: Clone + Display + PartialEq + 'static> {
+    /// Shows the screen until the driver moves on or backs out.
+    ///
+    /// A screen stays on the stack while the screens after it are shown, so any state it
+    /// keeps, such as a cursor, is still there if the driver comes back to it.
+    fn show<'a>(&'a mut self, flow: &'a mut Flow<R, C>) -> ScreenFuture<'a, R, C>;
+}
+
+/// What the selector does after a [`Screen`] is shown.
+pub enum Transition<R: 'static, C: Clone + Display + PartialEq + 'static> {
+    /// Moves on to the step after the point the screen was inserted at.
+    Next,
+    /// Opens another screen, which returns to this one when the driver backs out.
+    Push(Box<dyn Screen<R, C>>),
+    /// Swaps this screen for another, so backing out skips this one.
+    Replace(Box<dyn Screen<R, C>>),
+    /// Returns to the screen before this one.
+    Back,
+    /// Closes every screen down to the first one.
+    Home,
+    /// Shows this screen again.
+    Stay,
+}
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> Transition<R, C> {
+    /// Turns a push into a replace, for screens that shouldn't be returned to.
+    pub(crate) fn replacing(self) -> Self {
+        match self {
+            Transition::Push(screen) => Transition::Replace(screen),
+            transition => transition,
+        }
+    }
+}
+
+/// Where custom screens are inserted into the selection flow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FlowPoint {
+    /// After a route and its options are picked, before its checks run.
+    AfterRoute,
+    /// After the route, or the playlist, is confirmed and before the start delay.
+    AfterConfirm,
+}
+
+type ScreenFactory<R, C> = Rc<dyn Fn(&Flow<R, C>) -> Box<dyn Screen<R, C>>>;
+type FlowCondition<R, C> = Rc<dyn Fn(&Flow<R, C>) -> bool>;
+/// Creates the transition to the built-in step after a point.
+pub(crate) type After<R, C> = Rc<dyn Fn(&mut Flow<R, C>) -> Transition<R, C>>;
+
+/// A custom screen registered at a [`FlowPoint`].
+pub(crate) struct Insertion<R: 'static, C: Clone + Display + PartialEq + 'static> {
+    pub point: FlowPoint,
+    pub condition: Option<FlowCondition<R, C>>,
+    pub factory: ScreenFactory<R, C>,
+}
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> Insertion<R, C> {
+    pub fn new<S: Screen<R, C> + 'static>(
+        point: FlowPoint,
+        condition: Option<FlowCondition<R, C>>,
+        factory: impl Fn() -> S + 'static,
+    ) -> Self {
+        Self {
+            point,
+            condition,
+            factory: Rc::new(move |_| Box::new(factory())),
+        }
+    }
+}
+
+/// The state of the selection flow, shared by every [`Screen`].
+pub struct Flow<R: 'static, C: Clone + Display + PartialEq + 'static> {
+    pub(crate) ui: Rc<ControllerUi>,
+    pub(crate) state: Rc<RefCell<SelectorState<R, C>>>,
+    pub(crate) options: SelectorOptions,
+    /// Sorted categories of the routes
+    pub(crate) categories: Vec<C>,
+    pub(crate) has_devices: bool,
+    pub(crate) route_is_first: bool,
+    /// The route last highlighted in each category, for when it is opened again
+    pub(crate) route_cursors: Vec<usize>,
+    pub(crate) route: Option<PickedRoute<R, C>>,
+    /// Values chosen so far for the options of the route being picked
+    pub(crate) option_values: Vec<OptionValue>,
+    /// Routes added in playlist mode, with their option values
+    pub(crate) playlist: Vec<(PickedRoute<R, C>, RouteContext)>,
+    /// The playlist entry to highlight the next time the playlist is shown
+    pub(crate) playlist_cursor: Option<usize>,
+    answers: Vec<(String, OptionValue)>,
+    insertions: Vec<Insertion<R, C>>,
+}
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> Flow<R, C> {
+    pub(crate) fn new(
+        ui: Rc<ControllerUi>,
+        state: Rc<RefCell<SelectorState<R, C>>>,
+        options: SelectorOptions,
+        categories: Vec<C>,
+        insertions: Vec<Insertion<R, C>>,
+    ) -> Self {
+        let has_devices = !options.devices.is_empty()
+            || state.borrow().routes.iter().any(|r| !r.devices.is_empty());
+        let route_is_first =
+            !options.flat_list && options.skip_single_category && categories.len() == 1;
+        Self {
+            ui,
+            state,
+            has_devices,
+            route_is_first,
+            route_cursors: alloc::vec![0; categories.len()],
+            categories,
+            options,
+            route: None,
+            option_values: Vec::new(),
+            playlist: Vec::new(),
+            playlist_cursor: None,
+            answers: Vec::new(),
+            insertions,
+        }
+    }
+
+    /// Returns the UI the selector is shown on.
+    pub fn ui(&self) -> &ControllerUi {
+        &self.ui
+    }
+
+    /// Returns the route being picked, once the driver has chosen one.
+    pub fn route(&self) -> Option<&Route<R, C>> {
+        self.route.as_deref()
+    }
+
+    /// Returns an answer stored by a custom screen.
+    pub fn answer(&self, name: &str) -> Option<OptionValue> {
+        self.answers
+            .iter()
+            .find(|(answer, _)| answer == name)
+            .map(|(_, value)| *value)
+    }
+
+    /// Stores an answer, which is passed to the route in its [`RouteContext`].
+    pub fn set_answer(&mut self, name: impl Into<String>, value: OptionValue) {
+        let name = name.into();
+        match self.answers.iter_mut().find(|(answer, _)| *answer == name) {
+            Some((_, answer)) => *answer = value,
+            None => self.answers.push((name, value)),
+        }
+    }
+
+    /// Removes an answer, such as when the driver backs out of the screen that stored it.
+    pub fn clear_answer(&mut self, name: &str) {
+        self.answers.retain(|(answer, _)| answer != name);
+    }
+
+    /// Returns the screen the selector opens on.
+    pub(crate) fn first_screen(&self) -> Box<dyn Screen<R, C>> {
+        if self.options.playlist {
+            Box::new(PlaylistScreen::default())
+        } else {
+            self.pick_screen()
+        }
+    }
+
+    /// Returns the first list routes are picked from.
+    pub(crate) fn pick_screen(&self) -> Box<dyn Screen<R, C>> {
+        if self.options.flat_list {
+            Box::new(ListScreen::default())
+        } else if self.route_is_first {
+            Box::new(RouteScreen::new(self.categories[0].clone(), 0))
+        } else {
+            Box::new(CategoryScreen::default())
+        }
+    }
+
+    /// Shows the custom screens inserted at `point`, followed by the step created by `after`.
+    pub(crate) fn continue_to(&mut self, point: FlowPoint, after: After<R, C>) -> Transition<R, C> {
+        self.continue_from(point, 0, after)
+    }
+
+    fn continue_from(
+        &mut self,
+        point: FlowPoint,
+        from: usize,
+        after: After<R, C>,
+    ) -> Transition<R, C> {
+        let next = self
+            .insertions
+            .iter()
+            .enumerate()
+            .skip(from)
+            .find(|(_, insertion)| {
+                insertion.point == point
+                    && insertion
+                        .condition
+                        .as_ref()
+                        .is_none_or(|condition| condition(self))
+            });
+        match next {
+            Some((index, insertion)) => Transition::Push(Box::new(Inserted {
+                screen: (insertion.factory)(self),
+                point,
+                index,
+                after,
+            })),
+            None => after(self),
+        }
+    }
+
+    /// Returns the context a route runs with, including the answers of custom screens.
+    pub(crate) fn context(&self, route: &Route<R, C>, values: &[OptionValue]) -> RouteContext {
+        RouteContext::from_values(&route.options, values).with_answers(&self.answers)
+    }
+
+    /// Moves on from a route the driver has committed to.
+    pub(crate) fn confirmed(&mut self, route: PickedRoute<R, C>) -> Transition<R, C> {
+        if self.options.playlist {
+            let context = RouteContext::from_values(&route.options, &self.option_values);
+            self.playlist.push((route, context));
+            self.playlist_cursor = Some(self.playlist.len() - 1);
+            return Transition::Home;
+        }
+        self.continue_to(
+            FlowPoint::AfterConfirm,
+            Rc::new(move |flow| {
+                if flow.options.max_start_delay.is_some() {
+                    Transition::Push(Box::new(DelayScreen::new(Some(route.clone()))))
+                } else {
+                    flow.commit(Some(&route));
+                    Transition::Push(Box::new(DoneScreen::new(Some(route.clone()))))
+                }
+            }),
+        )
+    }
+
+    /// Moves on from the playlist once the driver confirms it.
+    pub(crate) fn playlist_confirmed(&mut self) -> Transition<R, C> {
+        self.continue_to(
+            FlowPoint::AfterConfirm,
+            Rc::new(|flow| {
+                if flow.options.max_start_delay.is_some() {
+                    Transition::Push(Box::new(DelayScreen::new(None)))
+                } else {
+                    flow.commit(None);
+                    Transition::Push(Box::new(DoneScreen::new(None)))
+                }
+            }),
+        )
+    }
+
+    /// Stores the route, or the playlist if `None`, as what runs when the selector is run.
+    pub(crate) fn commit(&mut self, route: Option<&PickedRoute<R, C>>) {
+        let mut state = self.state.borrow_mut();
+        match route {
+            Some(route) => {
+                let context = self.context(route, &self.option_values);
+                state.select_route(route.index, context);
+            }
+            None => {
+                let playlist = self
+                    .playlist
+                    .iter()
+                    .map(|(route, context)| {
+                        (route.index, context.clone().with_answers(&self.answers))
+                    })
+                    .collect::<Vec<_>>();
+                state.select_playlist(playlist);
+            }
+        }
+    }
+
+    /// Creates a picker which follows the selector's header options.
+    pub(crate) fn picker(&self, prompt: impl Into<String>, options: Vec<String>) -> Picker {
+        Picker::new(prompt, options).position_indicator(self.options.position_indicator)
+    }
+
+    /// Returns the title of a step of picking the current route, as a breadcrumb trail if
+    /// breadcrumbs are enabled.
+    pub(crate) fn breadcrumb(&self, title: &str) -> String {
+        match &self.route {
+            Some(route) if self.options.breadcrumbs => {
+                format!("{} > {} > {}", route.category, route.name, title)
+            }
+            _ => title.to_string(),
+        }
+    }
+
+    /// Adds the device health entry to the end of the first list.
+    pub(crate) fn with_devices(&self, mut options: Vec<String>) -> Vec<String> {
+        if self.has_devices {
+            options.push(self.options.devices_label.clone());
+        }
+        options
+    }
+}
+
+/// A custom screen, along with where the flow continues once it is done.
+struct Inserted<R: 'static, C: Clone + Display + PartialEq + 'static> {
+    screen: Box<dyn Screen<R, C>>,
+    point: FlowPoint,
+    index: usize,
+    after: After<R, C>,
+}
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> Screen<R, C> for Inserted<R, C> {
+    fn show<'a>(&'a mut self, flow: &'a mut Flow<R, C>) -> ScreenFuture<'a, R, C> {
+        Box::pin(async move {
+            match self.screen.show(flow).await {
+                Transition::Next => {
+                    flow.continue_from(self.point, self.index + 1, self.after.clone())
+                }
+                transition => transition,
+            }
+        })
+    }
+}
+
+/// A screen which asks the driver to set a [`RouteOption`] and stores it as an answer.
+///
+/// This is the quickest way to add a question to the flow:
+///
+/// ```ignore
+/// builder.screen(FlowPoint::AfterRoute, || {
+///     Ask::new(RouteOption::choice("Preload", ["Ring", "Goal"], 0))
+/// })
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ask {
+    option: RouteOption,
+}
+
+impl Ask {
+    pub fn new(option: RouteOption) -> Self {
+        Self { option }
+    }
+}
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> Screen<R, C> for Ask {
+    fn show<'a>(&'a mut self, flow: &'a mut Flow<R, C>) -> ScreenFuture<'a, R, C> {
+        Box::pin(async move {
+            let previous = flow.answer(&self.option.name);
+            match ask_option(flow, &self.option, previous).await {
+                Some(value) => {
+                    flow.set_answer(self.option.name.clone(), value);
+                    Transition::Next
+                }
+                None => {
+                    flow.clear_answer(&self.option.name);
+                    Transition::Back
+                }
+            }
+        })
+    }
+}
+
+/// Asks for the value of an option, starting on `previous` if it is set.
+pub(crate) async fn ask_option<R: 'static, C: Clone + Display + PartialEq + 'static>(
+    flow: &Flow<R, C>,
+    option: &RouteOption,
+    previous: Option<OptionValue>,
+) -> Option<OptionValue> {
+    let ui = flow.ui();
+    let prompt = flow.breadcrumb(&option.name);
+    match &option.kind {
+        OptionKind::Bool { default } => {
+            let value = match previous {
+                Some(OptionValue::Bool(value)) => value,
+                _ => *default,
+            };
+            Toggle::new(prompt, value)
+                .show(ui)
+                .await
+                .map(OptionValue::Bool)
+        }
+        OptionKind::Choice { choices, default } => {
+            let value = match previous {
+                Some(OptionValue::Choice(value)) => value,
+                _ => *default,
+            };
+            flow.picker(prompt, choices.clone())
+                .selected(value)
+                .show(ui)
+                .await
+                .map(OptionValue::Choice)
+        }
+        OptionKind::Integer { min, max, default } => {
+            let value = match previous {
+                Some(OptionValue::Integer(value)) => value,
+                _ => *default,
+            };
+            Spinner::new(prompt, *min as f64, *max as f64, 1.0)
+                .value(value as f64)
+                .show(ui)
+                .await
+                .map(|value| OptionValue::Integer(value as i32))
+        }
+    }
+}
@@ -0,0 +1,513 @@
+//! The built-in screens of the selection flow
+
+use alloc::{boxed::Box, format, rc::Rc, string::ToString, vec::Vec};
+use core::{fmt::Display, ops::Deref, time::Duration};
+
+use crate::{
+    diagnostics::{scan_devices, show_device_health, DeviceIssue},
+    platform::{brain_battery, sleep},
+    screen::{ask_option, Flow, FlowPoint, Screen, ScreenFuture, Transition},
+    widgets::{Confirm, Dialog, Picker, Spinner},
+    Route,
+};
+
+/// How long each frame of the done screen is shown, which is how fast its battery warning
+/// blinks.
+const DONE_FRAME: Duration = Duration::from_millis(600);
+/// How much each press changes the start delay by.
+pub(crate) const START_DELAY_STEP: Duration = Duration::from_millis(500);
+
+/// A route along with its index in the route table.
+///
+/// Routes are stored by index once picked, since routes computed at runtime may share a
+/// category and name.
+pub(crate) struct PickedRoute<R, C: Clone + Display + PartialEq> {
+    pub index: usize,
+    pub route: Route<R, C>,
+}
+
+impl<R, C: Clone + Display + PartialEq> Clone for PickedRoute<R, C> {
+    fn clone(&self) -> Self {
+        Self {
+            index: self.index,
+            route: self.route.clone(),
+        }
+    }
+}
+
+impl<R, C: Clone + Display + PartialEq> Deref for PickedRoute<R, C> {
+    type Target = Route<R, C>;
+
+    fn deref(&self) -> &Route<R, C> {
+        &self.route
+    }
+}
+
+/// Returns the routes in `category` along with their indices.
+fn routes_in<'a, R, C: Clone + Display + PartialEq>(
+    routes: &'a [Route<R, C>],
+    category: &'a C,
+) -> impl Iterator<Item = PickedRoute<R, C>> + 'a {
+    routes
+        .iter()
+        .enumerate()
+        .filter(move |(_, route)| route.category == *category)
+        .map(|(index, route)| PickedRoute {
+            index,
+            route: route.clone(),
+        })
+}
+
+/// Moves on from a route picked from a list, asking for its options first.
+fn route_picked<R: 'static, C: Clone + Display + PartialEq + 'static>(
+    flow: &mut Flow<R, C>,
+    route: PickedRoute<R, C>,
+) -> Transition<R, C> {
+    flow.option_values.clear();
+    flow.route = Some(route.clone());
+    Transition::Push(Box::new(OptionScreen { route, index: 0 }))
+}
+
+/// Every route in a single list, grouped by category.
+#[derive(Default)]
+pub(crate) struct ListScreen {
+    cursor: usize,
+}
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> Screen<R, C> for ListScreen {
+    fn show<'a>(&'a mut self, flow: &'a mut Flow<R, C>) -> ScreenFuture<'a, R, C> {
+        Box::pin(async move {
+            // Group the routes by category, in the same order as the categories
+            let routes = flow.state.borrow().routes.clone();
+            let mut sorted_routes = Vec::with_capacity(routes.len());
+            let mut group_starts = Vec::with_capacity(flow.categories.len());
+            for category in &flow.categories {
+                group_starts.push(sorted_routes.len());
+                sorted_routes.extend(routes_in(&routes, category));
+            }
+            let mut picker = flow
+                .picker(
+                    flow.options.list_prompt.clone(),
+                    flow.with_devices(
+                        sorted_routes
+                            .iter()
+                            .map(|route| format!("{} / {}", route.category, route.name))
+                            .collect(),
+                    ),
+                )
+                .groups(group_starts)
+                .selected(self.cursor);
+            let selected = picker.pick(&flow.ui).await;
+            self.cursor = picker.cursor();
+            match selected {
+                Some(selected) if selected == sorted_routes.len() => {
+                    Transition::Push(Box::new(DevicesScreen))
+                }
+                Some(selected) => route_picked(flow, sorted_routes.swap_remove(selected)),
+                None => Transition::Back,
+            }
+        })
+    }
+}
+
+/// The list of categories.
+#[derive(Default)]
+pub(crate) struct CategoryScreen {
+    cursor: usize,
+}
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> Screen<R, C> for CategoryScreen {
+    fn show<'a>(&'a mut self, flow: &'a mut Flow<R, C>) -> ScreenFuture<'a, R, C> {
+        Box::pin(async move {
+            let mut picker = flow
+                .picker(
+                    flow.options.category_prompt.clone(),
+                    flow.with_devices(flow.categories.iter().map(|c| c.to_string()).collect()),
+                )
+                .selected(self.cursor);
+            let selected = picker.pick(&flow.ui).await;
+            self.cursor = picker.cursor();
+            match selected {
+                Some(selected) if selected == flow.categories.len() => {
+                    Transition::Push(Box::new(DevicesScreen))
+                }
+                Some(selected) => Transition::Push(Box::new(RouteScreen::new(
+                    flow.categories[selected].clone(),
+                    flow.route_cursors[selected],
+                ))),
+                None => Transition::Back,
+            }
+        })
+    }
+}
+
+/// The routes of one category.
+pub(crate) struct RouteScreen<C> {
+    category: C,
+    cursor: usize,
+}
+
+impl<C> RouteScreen<C> {
+    pub fn new(category: C, cursor: usize) -> Self {
+        Self { category, cursor }
+    }
+}
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> Screen<R, C> for RouteScreen<C> {
+    fn show<'a>(&'a mut self, flow: &'a mut Flow<R, C>) -> ScreenFuture<'a, R, C> {
+        Box::pin(async move {
+            let route_picker_header = if flow.options.breadcrumbs {
+                self.category.to_string()
+            } else {
+                format!("- {} -", self.category)
+            };
+            let routes = flow.state.borrow().routes.clone();
+            let mut filtered_routes = routes_in(&routes, &self.category).collect::<Vec<_>>();
+            let mut names = filtered_routes
+                .iter()
+                .map(|route| route.name.to_string())
+                .collect::<Vec<_>>();
+            if flow.route_is_first {
+                names = flow.with_devices(names);
+            }
+            let mut picker = flow
+                .picker(route_picker_header, names)
+                .selected(self.cursor);
+            let selected = picker.pick(&flow.ui).await;
+            self.cursor = picker.cursor();
+            if let Some(index) = flow.categories.iter().position(|c| *c == self.category) {
+                flow.route_cursors[index] = self.cursor;
+            }
+            match selected {
+                Some(selected) if selected == filtered_routes.len() => {
+                    Transition::Push(Box::new(DevicesScreen))
+                }
+                Some(selected) => route_picked(flow, filtered_routes.swap_remove(selected)),
+                None => Transition::Back,
+            }
+        })
+    }
+}
+
+/// The health of the expected devices.
+pub(crate) struct DevicesScreen;
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> Screen<R, C> for DevicesScreen {
+    fn show<'a>(&'a mut self, flow: &'a mut Flow<R, C>) -> ScreenFuture<'a, R, C> {
+        Box::pin(async move {
+            let mut expected = flow.options.devices.clone();
+            {
+                let state = flow.state.borrow();
+                if let Some(selected) = state.selection {
+                    expected.extend(state.routes[selected].devices.clone());
+                }
+            }
+            show_device_health(&flow.ui, &expected).await;
+            Transition::Back
+        })
+    }
+}
+
+/// Asks for the value of the route's option at an index.
+pub(crate) struct OptionScreen<R: 'static, C: Clone + Display + PartialEq + 'static> {
+    route: PickedRoute<R, C>,
+    index: usize,
+}
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> Screen<R, C> for OptionScreen<R, C> {
+    fn show<'a>(&'a mut self, flow: &'a mut Flow<R, C>) -> ScreenFuture<'a, R, C> {
+        Box::pin(async move {
+            // Start on the value picked before, if the driver came back to it
+            let previous = flow.option_values.get(self.index).copied();
+            flow.option_values.truncate(self.index);
+            let Some(option) = self.route.options.get(self.index) else {
+                let route = self.route.clone();
+                return flow
+                    .continue_to(
+                        FlowPoint::AfterRoute,
+                        Rc::new(move |_| {
+                            Transition::Push(Box::new(ChecksScreen {
+                                route: route.clone(),
+                            }))
+                        }),
+                    )
+                    .replacing();
+            };
+            match ask_option(flow, option, previous).await {
+                Some(value) => {
+                    flow.option_values.push(value);
+                    Transition::Push(Box::new(OptionScreen {
+                        route: self.route.clone(),
+                        index: self.index + 1,
+                    }))
+                }
+                None => Transition::Back,
+            }
+        })
+    }
+}
+
+/// Runs the pre-flight checks of a route, listing any that fail.
+pub(crate) struct ChecksScreen<R: 'static, C: Clone + Display + PartialEq + 'static> {
+    route: PickedRoute<R, C>,
+}
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> Screen<R, C> for ChecksScreen<R, C> {
+    fn show<'a>(&'a mut self, flow: &'a mut Flow<R, C>) -> ScreenFuture<'a, R, C> {
+        Box::pin(async move {
+            let route = &self.route;
+            let checks = &flow.options.checks;
+            let devices = &flow.options.devices;
+            let mut failed = Vec::new();
+            let has_checks = !checks.is_empty() || !route.checks.is_empty();
+            if has_checks || !devices.is_empty() || !route.devices.is_empty() {
+                Dialog::new("- Checking -", route.name.clone())
+                    .draw(&flow.ui)
+                    .await;
+                for check in checks.iter().chain(&route.checks) {
+                    if !check.run().await {
+                        failed.push(check.label.clone());
+                    }
+                }
+                failed.extend(
+                    scan_devices(devices.iter().chain(&route.devices))
+                        .iter()
+                        .map(DeviceIssue::summary),
+                );
+            }
+
+            if failed.is_empty() {
+                return if flow.options.skip_confirm || flow.options.playlist {
+                    flow.confirmed(route.clone()).replacing()
+                } else {
+                    Transition::Replace(Box::new(ConfirmScreen {
+                        route: route.clone(),
+                    }))
+                };
+            }
+            // List the failures, followed by the ways out
+            let header = match failed.len() {
+                1 => "1 check failed".to_string(),
+                n => format!("{} checks failed", n),
+            };
+            failed.push("Retry".to_string());
+            failed.push(flow.options.override_label.clone());
+            let override_index = failed.len() - 1;
+            match Picker::new(header, failed).show(&flow.ui).await {
+                Some(i) if i == override_index => flow.confirmed(route.clone()),
+                Some(_) => Transition::Stay,
+                None => Transition::Back,
+            }
+        })
+    }
+}
+
+/// Asks the driver to confirm a route.
+pub(crate) struct ConfirmScreen<R: 'static, C: Clone + Display + PartialEq + 'static> {
+    route: PickedRoute<R, C>,
+}
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> Screen<R, C> for ConfirmScreen<R, C> {
+    fn show<'a>(&'a mut self, flow: &'a mut Flow<R, C>) -> ScreenFuture<'a, R, C> {
+        Box::pin(async move {
+            let confirm = Confirm::with_labels(
+                format!("Pick in {}:", self.route.category),
+                flow.options.cancel_label.clone(),
+                format!("{} {}", flow.options.confirm_label, self.route.name),
+            );
+            match confirm.show(&flow.ui).await {
+                Some(true) => flow.confirmed(self.route.clone()),
+                _ => Transition::Back,
+            }
+        })
+    }
+}
+
+/// The routes added in playlist mode.
+#[derive(Default)]
+pub(crate) struct PlaylistScreen {
+    cursor: usize,
+}
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> Screen<R, C> for PlaylistScreen {
+    fn show<'a>(&'a mut self, flow: &'a mut Flow<R, C>) -> ScreenFuture<'a, R, C> {
+        Box::pin(async move {
+            if let Some(cursor) = flow.playlist_cursor.take() {
+                self.cursor = cursor;
+            }
+            let playlist = &flow.playlist;
+            let mut entries = playlist
+                .iter()
+                .enumerate()
+                .map(|(i, (route, _))| format!("{}. {} / {}", i + 1, route.category, route.name))
+                .collect::<Vec<_>>();
+            entries.push("+ Add route".to_string());
+            if !playlist.is_empty() {
+                entries.push(flow.options.confirm_label.clone());
+            }
+            let mut picker = flow
+                .picker(format!("- Playlist ({}) -", playlist.len()), entries)
+                .selected(self.cursor);
+            let selected = picker.pick(&flow.ui).await;
+            self.cursor = picker.cursor();
+            match selected {
+                Some(i) if i < flow.playlist.len() => {
+                    Transition::Push(Box::new(PlaylistEntryScreen {
+                        index: i,
+                        cursor: 0,
+                    }))
+                }
+                Some(i) if i == flow.playlist.len() => Transition::Push(flow.pick_screen()),
+                Some(_) => flow.playlist_confirmed(),
+                None => Transition::Stay,
+            }
+        })
+    }
+}
+
+/// Moves or removes an entry of the playlist.
+pub(crate) struct PlaylistEntryScreen {
+    index: usize,
+    cursor: usize,
+}
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> Screen<R, C> for PlaylistEntryScreen {
+    fn show<'a>(&'a mut self, flow: &'a mut Flow<R, C>) -> ScreenFuture<'a, R, C> {
+        Box::pin(async move {
+            let index = self.index;
+            let playlist = &mut flow.playlist;
+            let header = format!("{}. {}", index + 1, playlist[index].0.name);
+            let actions = ["Move up", "Move down", "Remove"]
+                .map(ToString::to_string)
+                .to_vec();
+            let mut picker = Picker::new(header, actions).selected(self.cursor);
+            let selected = picker.pick(&flow.ui).await;
+            self.cursor = picker.cursor();
+            // Keep the playlist highlighting the entry that was edited
+            flow.playlist_cursor = match selected {
+                Some(0) if index > 0 => {
+                    playlist.swap(index, index - 1);
+                    Some(index - 1)
+                }
+                Some(1) if index + 1 < playlist.len() => {
+                    playlist.swap(index, index + 1);
+                    Some(index + 1)
+                }
+                Some(2) => {
+                    playlist.remove(index);
+                    Some(index)
+                }
+                _ => None,
+            };
+            Transition::Back
+        })
+    }
+}
+
+/// Asks for the start delay of a route, or of the playlist if `route` is `None`.
+pub(crate) struct DelayScreen<R: 'static, C: Clone + Display + PartialEq + 'static> {
+    route: Option<PickedRoute<R, C>>,
+}
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> DelayScreen<R, C> {
+    pub fn new(route: Option<PickedRoute<R, C>>) -> Self {
+        Self { route }
+    }
+}
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> Screen<R, C> for DelayScreen<R, C> {
+    fn show<'a>(&'a mut self, flow: &'a mut Flow<R, C>) -> ScreenFuture<'a, R, C> {
+        Box::pin(async move {
+            let spinner = Spinner::new(
+                "- Start delay -",
+                0.0,
+                flow.options
+                    .max_start_delay
+                    .unwrap_or_default()
+                    .as_secs_f64(),
+                START_DELAY_STEP.as_secs_f64(),
+            )
+            .value(flow.state.borrow().start_delay.as_secs_f64())
+            .decimals(1)
+            .suffix("s");
+            match spinner.show(&flow.ui).await {
+                Some(delay) => {
+                    flow.commit(self.route.as_ref());
+                    flow.state.borrow_mut().start_delay = Duration::from_secs_f64(delay);
+                    Transition::Push(Box::new(DoneScreen::new(self.route.clone())))
+                }
+                None => Transition::Back,
+            }
+        })
+    }
+}
+
+/// Shows the committed route, or the playlist if `route` is `None`.
+pub(crate) struct DoneScreen<R: 'static, C: Clone + Display + PartialEq + 'static> {
+    route: Option<PickedRoute<R, C>>,
+    /// Number of times the dialog has been drawn
+    frame: u32,
+    rumbled: bool,
+}
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> DoneScreen<R, C> {
+    pub fn new(route: Option<PickedRoute<R, C>>) -> Self {
+        Self {
+            route,
+            frame: 0,
+            rumbled: false,
+        }
+    }
+}
+
+impl<R: 'static, C: Clone + Display + PartialEq + 'static> Screen<R, C> for DoneScreen<R, C> {
+    fn show<'a>(&'a mut self, flow: &'a mut Flow<R, C>) -> ScreenFuture<'a, R, C> {
+        Box::pin(async move {
+            let ui = &flow.ui;
+            let options = &flow.options;
+            let mut description = match &self.route {
+                Some(route) => format!("{} / {}", route.category, route.name),
+                None => format!("{} routes", flow.playlist.len()),
+            };
+            let start_delay = flow.state.borrow().start_delay;
+            if !start_delay.is_zero() {
+                description.push_str(&format!(" +{:.1}s", start_delay.as_secs_f64()));
+            }
+            if let Some(route) = &self.route {
+                let changed_options = flow.state.borrow().context.summary(&route.options);
+                if !changed_options.is_empty() {
+                    description.push(' ');
+                    description.push_str(&changed_options);
+                }
+            }
+            let mut dialog = Dialog::new(options.done_title.clone(), description);
+            if let Some(threshold) = options.battery_threshold {
+                let brain = brain_battery();
+                let controller = ui.controller_battery();
+                let low = brain < threshold || controller.is_some_and(|level| level < threshold);
+                if low && !self.rumbled {
+                    self.rumbled = ui.try_rumble("- -");
+                }
+                // Blink the warning by alternating it with the levels
+                dialog = dialog.supporting(if low && self.frame % 2 == 0 {
+                    "! LOW BATTERY !".to_string()
+                } else {
+                    format!(
+                        "Brain {:.0}% Ctrl {}",
+                        brain * 100.0,
+                        controller.map_or("--".to_string(), |level| {
+                            format!("{:.0}%", level * 100.0)
+                        })
+                    )
+                });
+            } else if let Some(message) = &options.done_message {
+                dialog = dialog.supporting(message.clone());
+            }
+            ui.set_active(true);
+            dialog.draw(ui).await;
+            sleep(DONE_FRAME).await;
+            self.frame = self.frame.wrapping_add(1);
+            Transition::Stay
+        })
+    }
+}
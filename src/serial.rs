@@ -0,0 +1,471 @@
+//! Listing and selecting routes from a computer over a serial link
+//!
+//! When a selector is built with [`ControllerSelectBuilder::serial`], it also answers a simple
+//! line protocol on that link, which on the robot is usually the USB connection's stdin and
+//! stdout. Each request is one line:
+//!
+//! | Request                    | Response                                                     |
+//! |----------------------------|--------------------------------------------------------------|
+//! | `list`                     | `@route <category>/<name>` for every route, then `@ok`       |
+//! | `get`                      | `@selected <category>/<name>;...`                            |
+//! | `select <category>/<name>` | `@ok`, or `@err <message>` if there is no such route         |
+//! | `subscribe`                | `@ok`, then `@changed <category>/<name>;...` on every change |
+//! | `unsubscribe`              | `@ok`                                                        |
+//! | `press <action>`           | `@ok`, after which the menu handles the action               |
+//!
+//! A selection lists each route to run separated by `;`, which is several routes for a
+//! playlist and none before anything is selected. Routes are matched on the displayed category
+//! and the name, split at the first `/`. Every response starts with `@`, so a client can skip
+//! whatever else the robot prints on the same link.
+//!
+//! Since names can be computed at runtime, `\`, `/` and `;` in a category or name are sent
+//! escaped with a backslash, and line breaks as `\n` and `\r`.
+//!
+//! `press` navigates the controller menu as if a button had been pressed. The action is one of
+//! `next`, `previous`, `confirm`, `back`, `next-group`, `previous-group`, `exit`, `reopen` or
+//! `jump <index>`.
+//!
+//! [`ControllerSelectBuilder::serial`]: crate::ControllerSelectBuilder::serial
+
+use alloc::{
+    boxed::Box,
+    collections::VecDeque,
+    format,
+    rc::Rc,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    cell::RefCell,
+    fmt::{self, Display, Write as _},
+    time::Duration,
+};
+
+use crate::{input::Events, platform::sleep, Action, SelectorState};
+
+/// A byte stream the protocol is spoken over.
+pub trait SerialLink {
+    /// Reads bytes which have already arrived into `buf` without waiting, returning how many
+    /// were read.
+    fn read(&mut self, buf: &mut [u8]) -> usize;
+
+    /// Writes as many of `bytes` as can be sent without waiting, returning how many were
+    /// written. The rest are tried again on the next poll.
+    fn write(&mut self, bytes: &[u8]) -> usize;
+}
+
+impl<T: SerialLink + ?Sized> SerialLink for Box<T> {
+    fn read(&mut self, buf: &mut [u8]) -> usize {
+        (**self).read(buf)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> usize {
+        (**self).write(bytes)
+    }
+}
+
+/// The robot's stdin and stdout, which are sent over its USB connection.
+#[cfg(target_vendor = "vex")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stdio;
+
+#[cfg(target_vendor = "vex")]
+impl SerialLink for Stdio {
+    fn read(&mut self, buf: &mut [u8]) -> usize {
+        use vexide::io::Read;
+
+        vexide::io::stdin()
+            .try_lock()
+            .and_then(|mut stdin| stdin.read(buf).ok())
+            .unwrap_or(0)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> usize {
+        use vexide::io::Write;
+
+        // Something else printing holds the lock, so leave the bytes for the next poll
+        vexide::io::stdout()
+            .try_lock()
+            .and_then(|mut stdout| {
+                let written = stdout.write(bytes).ok()?;
+                _ = stdout.flush();
+                Some(written)
+            })
+            .unwrap_or(0)
+    }
+}
+
+/// A route named by its displayed category and its name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RouteRef {
+    pub category: String,
+    pub name: String,
+}
+
+impl RouteRef {
+    pub fn new(category: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            category: category.into(),
+            name: name.into(),
+        }
+    }
+
+    /// Parses `category/name`, splitting at the first `/` which isn't escaped.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (category, name) = split_once_unescaped(s, '/')?;
+        Some(Self::new(unescape(category.trim()), unescape(name.trim())))
+    }
+}
+
+impl Display for RouteRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        escape(&self.category, f)?;
+        f.write_char('/')?;
+        escape(&self.name, f)
+    }
+}
+
+/// Writes `s` with the characters that separate routes, or end a line, escaped.
+fn escape(s: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for c in s.chars() {
+        match c {
+            '\\' | '/' | ';' => {
+                f.write_char('\\')?;
+                f.write_char(c)?;
+            }
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            c => f.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+/// Reverses [`escape`].
+fn unescape(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(escaped) => result.push(escaped),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Splits `s` at the first `separator` which isn't escaped.
+fn split_once_unescaped(s: &str, separator: char) -> Option<(&str, &str)> {
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            _ if c == separator => return Some((&s[..i], &s[i + c.len_utf8()..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// A request sent to the robot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    List,
+    Get,
+    Select(RouteRef),
+    Subscribe,
+    Unsubscribe,
+    Press(Action),
+}
+
+impl Request {
+    /// Parses a request line, returning a message describing the problem if it isn't valid.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+        let argument = argument.trim();
+        let request = match command.to_ascii_lowercase().as_str() {
+            "list" => Request::List,
+            "get" => Request::Get,
+            "select" => Request::Select(
+                RouteRef::parse(argument).ok_or("expected `select <category>/<name>`")?,
+            ),
+            "subscribe" => Request::Subscribe,
+            "unsubscribe" => Request::Unsubscribe,
+            "press" => Request::Press(parse_action(argument).ok_or("expected `press <action>`")?),
+            _ => return Err(format!("unknown request `{command}`")),
+        };
+        if !argument.is_empty() && !matches!(request, Request::Select(_) | Request::Press(_)) {
+            return Err(format!("`{command}` doesn't take an argument"));
+        }
+        Ok(request)
+    }
+}
+
+impl Display for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Request::List => write!(f, "list"),
+            Request::Get => write!(f, "get"),
+            Request::Select(route) => write!(f, "select {route}"),
+            Request::Subscribe => write!(f, "subscribe"),
+            Request::Unsubscribe => write!(f, "unsubscribe"),
+            Request::Press(Action::Jump(index)) => write!(f, "press jump {index}"),
+            Request::Press(action) => write!(f, "press {}", action_name(*action)),
+        }
+    }
+}
+
+/// Returns the name of an action in a `press` request, other than [`Action::Jump`].
+const fn action_name(action: Action) -> &'static str {
+    match action {
+        Action::Next => "next",
+        Action::Previous => "previous",
+        Action::Confirm => "confirm",
+        Action::Back => "back",
+        Action::NextGroup => "next-group",
+        Action::PreviousGroup => "previous-group",
+        Action::Exit => "exit",
+        Action::Reopen => "reopen",
+        Action::Jump(_) => "jump",
+    }
+}
+
+fn parse_action(s: &str) -> Option<Action> {
+    let (name, argument) = s.split_once(' ').unwrap_or((s, ""));
+    let action = match name.to_ascii_lowercase().as_str() {
+        "next" => Action::Next,
+        "previous" => Action::Previous,
+        "confirm" => Action::Confirm,
+        "back" => Action::Back,
+        "next-group" => Action::NextGroup,
+        "previous-group" => Action::PreviousGroup,
+        "exit" => Action::Exit,
+        "reopen" => Action::Reopen,
+        "jump" => return argument.trim().parse().ok().map(Action::Jump),
+        _ => return None,
+    };
+    argument.trim().is_empty().then_some(action)
+}
+
+/// A line sent back by the robot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    /// One of the routes, in reply to [`Request::List`].
+    Route(RouteRef),
+    /// The current selection, in reply to [`Request::Get`].
+    Selected(Vec<RouteRef>),
+    /// The selection after it changed, while subscribed.
+    Changed(Vec<RouteRef>),
+    Ok,
+    Err(String),
+}
+
+impl Response {
+    /// Parses a response line, returning `None` for lines which aren't part of the protocol.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end().strip_prefix('@')?;
+        let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let routes = || {
+            let mut routes = Vec::new();
+            let mut rest = rest;
+            while !rest.trim().is_empty() {
+                let (route, remaining) = split_once_unescaped(rest, ';').unwrap_or((rest, ""));
+                routes.push(RouteRef::parse(route)?);
+                rest = remaining;
+            }
+            Some(routes)
+        };
+        Some(match kind {
+            "route" => Response::Route(RouteRef::parse(rest)?),
+            "selected" => Response::Selected(routes()?),
+            "changed" => Response::Changed(routes()?),
+            "ok" => Response::Ok,
+            "err" => Response::Err(rest.to_string()),
+            _ => return None,
+        })
+    }
+}
+
+impl Display for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |routes: &[RouteRef]| {
+            routes
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(";")
+        };
+        match self {
+            Response::Route(route) => write!(f, "@route {route}"),
+            Response::Selected(routes) => write!(f, "@selected {}", join(routes)),
+            Response::Changed(routes) => write!(f, "@changed {}", join(routes)),
+            Response::Ok => write!(f, "@ok"),
+            Response::Err(message) => write!(f, "@err {message}"),
+        }
+    }
+}
+
+/// The longest request line accepted. Longer lines are answered with an error.
+const MAX_LINE: usize = 256;
+
+/// The most bytes waiting to be sent. Responses beyond this are dropped, since nothing is
+/// reading them.
+const MAX_PENDING: usize = 4096;
+
+/// Responses waiting for the link to accept them.
+#[derive(Default)]
+struct Outbox(VecDeque<u8>);
+
+impl Outbox {
+    fn push(&mut self, response: &Response) {
+        let line = format!("{response}\n");
+        if self.0.len() + line.len() <= MAX_PENDING {
+            self.0.extend(line.as_bytes());
+        }
+    }
+
+    /// Sends as much as the link accepts.
+    fn flush(&mut self, link: &mut impl SerialLink) {
+        while !self.0.is_empty() {
+            let sent = link.write(self.0.make_contiguous());
+            if sent == 0 {
+                break;
+            }
+            self.0.drain(..sent.min(self.0.len()));
+        }
+    }
+}
+
+/// Answers requests on `link` until the selector is dropped.
+pub(crate) async fn serve<R, C: Clone + Display + PartialEq + 'static>(
+    mut link: impl SerialLink,
+    state: Rc<RefCell<SelectorState<R, C>>>,
+    events: Events,
+    poll: Duration,
+) {
+    let mut line = Vec::new();
+    let mut outbox = Outbox::default();
+    let mut overflowed = false;
+    let mut subscribed = false;
+    let mut last_selection = selection(&state.borrow());
+    let mut buf = [0; 64];
+    loop {
+        loop {
+            let count = link.read(&mut buf);
+            if count == 0 {
+                break;
+            }
+            for &byte in &buf[..count] {
+                match byte {
+                    b'\n' => {
+                        let responses = if overflowed {
+                            vec![Response::Err("request is too long".to_string())]
+                        } else {
+                            let request = String::from_utf8_lossy(&line);
+                            handle(&request, &state, &events, &mut subscribed)
+                        };
+                        for response in &responses {
+                            outbox.push(response);
+                        }
+                        line.clear();
+                        overflowed = false;
+                    }
+                    b'\r' => {}
+                    _ if line.len() >= MAX_LINE => overflowed = true,
+                    _ => line.push(byte),
+                }
+            }
+        }
+
+        let current = selection(&state.borrow());
+        if current != last_selection {
+            if subscribed {
+                outbox.push(&Response::Changed(current.clone()));
+            }
+            last_selection = current;
+        }
+        outbox.flush(&mut link);
+        sleep(poll).await;
+    }
+}
+
+/// Returns the routes that will run.
+fn selection<R, C: Clone + Display + PartialEq>(state: &SelectorState<R, C>) -> Vec<RouteRef> {
+    let indices = match state.selection {
+        Some(index) => vec![index],
+        None => state.playlist.iter().map(|(index, _)| *index).collect(),
+    };
+    indices
+        .into_iter()
+        .map(|index| {
+            let route = &state.routes[index];
+            RouteRef::new(route.category.to_string(), route.name.clone())
+        })
+        .collect()
+}
+
+fn handle<R, C: Clone + Display + PartialEq + 'static>(
+    line: &str,
+    state: &RefCell<SelectorState<R, C>>,
+    events: &Events,
+    subscribed: &mut bool,
+) -> Vec<Response> {
+    let request = match Request::parse(line) {
+        Ok(request) => request,
+        // Ignore empty lines, which terminals send freely
+        Err(_) if line.trim().is_empty() => return Vec::new(),
+        Err(message) => return vec![Response::Err(message)],
+    };
+    match request {
+        Request::List => {
+            let state = state.borrow();
+            let mut responses = state
+                .routes
+                .iter()
+                .map(|route| {
+                    Response::Route(RouteRef::new(
+                        route.category.to_string(),
+                        route.name.clone(),
+                    ))
+                })
+                .collect::<Vec<_>>();
+            responses.push(Response::Ok);
+            responses
+        }
+        Request::Get => vec![Response::Selected(selection(&state.borrow()))],
+        Request::Select(target) => {
+            let mut state = state.borrow_mut();
+            let index = state.routes.iter().position(|route| {
+                route.category.to_string() == target.category && route.name == target.name
+            });
+            match index {
+                Some(index) => {
+                    state.select_index(index);
+                    vec![Response::Ok]
+                }
+                None => vec![Response::Err(format!("no route named `{target}`"))],
+            }
+        }
+        Request::Subscribe => {
+            *subscribed = true;
+            vec![Response::Ok]
+        }
+        Request::Unsubscribe => {
+            *subscribed = false;
+            vec![Response::Ok]
+        }
+        Request::Press(action) => {
+            events.push(action);
+            vec![Response::Ok]
+        }
+    }
+}
@@ -0,0 +1,60 @@
+//! Sharing the controller between the selector and robot code
+
+use alloc::{rc::Rc, sync::Arc};
+use core::cell::RefCell;
+
+#[cfg(target_vendor = "vex")]
+use vexide::sync::Mutex;
+
+use crate::platform::Controller;
+
+/// A controller which may also be used by other code while the selector runs.
+///
+/// The selector never waits on or panics over a controller that is in use elsewhere. If
+/// [`try_with`](Self::try_with) can't get the controller, the selector skips that read or
+/// write and tries again on its next update.
+///
+/// This is implemented for [`RefCell<Controller>`] and vexide's [`Mutex<Controller>`], and for
+/// [`Rc`] and [`Arc`] pointers to either of them. Off the robot, `Controller` is the
+/// [emulator's](crate::emulator::Controller).
+pub trait SharedController {
+    /// Calls `f` with the controller unless it is in use, returning `false` if it was.
+    fn try_with(&self, f: &mut dyn FnMut(&mut Controller)) -> bool;
+}
+
+impl SharedController for RefCell<Controller> {
+    fn try_with(&self, f: &mut dyn FnMut(&mut Controller)) -> bool {
+        match self.try_borrow_mut() {
+            Ok(mut controller) => {
+                f(&mut controller);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(target_vendor = "vex")]
+impl SharedController for Mutex<Controller> {
+    fn try_with(&self, f: &mut dyn FnMut(&mut Controller)) -> bool {
+        match self.try_lock() {
+            Some(mut controller) => {
+                f(&mut controller);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<T: SharedController + ?Sized> SharedController for Rc<T> {
+    fn try_with(&self, f: &mut dyn FnMut(&mut Controller)) -> bool {
+        (**self).try_with(f)
+    }
+}
+
+impl<T: SharedController + ?Sized> SharedController for Arc<T> {
+    fn try_with(&self, f: &mut dyn FnMut(&mut Controller)) -> bool {
+        (**self).try_with(f)
+    }
+}
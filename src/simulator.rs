@@ -0,0 +1,227 @@
+//! Trying a selector in a terminal, without a robot
+//!
+//! [`run`] draws the controller's three screen lines in the terminal and maps keys onto the
+//! controller's buttons, using the [emulator](crate::emulator) in real time. Whenever a route
+//! is confirmed, the simulator prints what would run when autonomous starts.
+//!
+//! The selector is built by a closure which is handed the emulated controller, so the same
+//! function that builds the selector on the robot can be reused:
+//!
+//! ```ignore
+//! fn selector(
+//!     controller: impl SharedController + 'static,
+//!     is_selecting: Rc<RefCell<bool>>,
+//! ) -> ControllerSelect<Robot, Category, 4> {
+//!     ControllerSelect::builder(controller, is_selecting, routes())
+//!         .playlist(true)
+//!         .build()
+//! }
+//!
+//! fn main() -> std::io::Result<()> {
+//!     autons_controller::simulator::run(selector)
+//! }
+//! ```
+
+use alloc::{format, rc::Rc, string::String, vec::Vec};
+use core::{cell::RefCell, fmt::Display, time::Duration};
+use std::{
+    io::{self, Write},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Instant,
+};
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::Print,
+    terminal::{self, Clear, ClearType},
+};
+
+use crate::{
+    emulator::{self, Controller, TAP},
+    Button, ControllerSelect,
+};
+
+/// How often the simulator reads keys and redraws.
+const FRAME: Duration = Duration::from_millis(10);
+
+/// The keys shown below the screen.
+const HELP: &str = "arrows: d-pad   a/enter: A   b/backspace: B   x, y: X, Y\r\n\
+                    [ ]: L1, R1   { }: L2, R2   p: power   q: quit";
+
+/// Runs a selector in the terminal until `q` or Ctrl-C is pressed.
+///
+/// `build` is called with the emulated controller and the selecting flag, and should build
+/// the selector the same way the robot does.
+///
+/// # Errors
+///
+/// Returns an error if the terminal can't be switched into raw mode or written to.
+pub fn run<R, C, const N: usize>(
+    build: impl FnOnce(Rc<RefCell<Controller>>, Rc<RefCell<bool>>) -> ControllerSelect<R, C, N>,
+) -> io::Result<()>
+where
+    R: 'static,
+    C: Clone + Display + PartialEq + PartialOrd + 'static,
+{
+    let controller = Rc::new(RefCell::new(Controller::new()));
+    let selector = build(controller.clone(), Rc::new(RefCell::new(true)));
+
+    let _terminal = RawTerminal::enable()?;
+    let keys = read_keys();
+    let mut log: Vec<String> = Vec::new();
+    let mut drawn = None;
+    let mut selection = String::new();
+    let mut last_frame = Instant::now();
+
+    loop {
+        let now = emulator::Instant::now().since_start();
+        while let Ok(key) = keys.try_recv() {
+            match key {
+                Key::Quit => return Ok(()),
+                Key::Button(button) => {
+                    controller.borrow_mut().press(button, now, TAP);
+                }
+            }
+        }
+
+        emulator::advance(last_frame.elapsed());
+        last_frame = Instant::now();
+
+        let current = describe_selection(&selector);
+        if current != selection {
+            if !current.is_empty() {
+                log.push(current.clone());
+            }
+            selection = current;
+            drawn = None;
+        }
+
+        let frame = controller.borrow().screen.frame();
+        if drawn.as_ref() != Some(&frame) {
+            draw(&frame, &log)?;
+            drawn = Some(frame);
+        }
+        thread::sleep(FRAME);
+    }
+}
+
+/// Describes what would run when autonomous starts, or returns an empty string if nothing is
+/// selected.
+fn describe_selection<R, C, const N: usize>(selector: &ControllerSelect<R, C, N>) -> String
+where
+    R: 'static,
+    C: Clone + Display + PartialEq + PartialOrd + 'static,
+{
+    let routes = selector
+        .selection()
+        .iter()
+        .map(|(route, context)| {
+            let summary = context.summary(&route.options);
+            if summary.is_empty() {
+                format!("{} > {}", route.category, route.name)
+            } else {
+                format!("{} > {} ({summary})", route.category, route.name)
+            }
+        })
+        .collect::<Vec<_>>();
+    if routes.is_empty() {
+        return String::new();
+    }
+    let delay = selector.start_delay();
+    let delay = if delay.is_zero() {
+        String::new()
+    } else {
+        format!(" after {:.1}s", delay.as_secs_f64())
+    };
+    format!("Would run {}{delay}", routes.join(", then "))
+}
+
+/// Clears the terminal and draws the controller screen, the keys and the log.
+fn draw(frame: &str, log: &[String]) -> io::Result<()> {
+    const WIDTH: usize = 28;
+    let mut out = format!("+{}+\r\n", "-".repeat(WIDTH));
+    for line in frame.split('\n') {
+        out.push_str(&format!("|{line:WIDTH$}|\r\n"));
+    }
+    out.push_str(&format!("+{}+\r\n\r\n{HELP}\r\n\r\n", "-".repeat(WIDTH)));
+    for entry in log.iter().rev().take(10).rev() {
+        out.push_str(entry);
+        out.push_str("\r\n");
+    }
+
+    let mut stdout = io::stdout().lock();
+    queue!(stdout, Clear(ClearType::All), MoveTo(0, 0))?;
+    stdout.write_all(out.as_bytes())?;
+    stdout.flush()
+}
+
+/// Something the user pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Button(Button),
+    Quit,
+}
+
+/// Reads keys from the terminal on a background thread.
+fn read_keys() -> Receiver<Key> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        while let Ok(event) = event::read() {
+            // Windows also reports releases, which would press every button twice
+            let Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            }) = event
+            else {
+                continue;
+            };
+            let key = match code {
+                KeyCode::Up => Key::Button(Button::Up),
+                KeyCode::Down => Key::Button(Button::Down),
+                KeyCode::Right => Key::Button(Button::Right),
+                KeyCode::Left => Key::Button(Button::Left),
+                // Ctrl-C arrives as a key since signals are turned off in raw mode
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => Key::Quit,
+                KeyCode::Char('a') | KeyCode::Enter => Key::Button(Button::A),
+                KeyCode::Char('b') | KeyCode::Backspace => Key::Button(Button::B),
+                KeyCode::Char('x') => Key::Button(Button::X),
+                KeyCode::Char('y') => Key::Button(Button::Y),
+                KeyCode::Char('[') => Key::Button(Button::L1),
+                KeyCode::Char('{') => Key::Button(Button::L2),
+                KeyCode::Char(']') => Key::Button(Button::R1),
+                KeyCode::Char('}') => Key::Button(Button::R2),
+                KeyCode::Char('p') => Key::Button(Button::Power),
+                KeyCode::Char('q') => Key::Quit,
+                _ => continue,
+            };
+            if sender.send(key).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Puts the terminal into raw mode and hides the cursor, restoring both when dropped.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enable() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let terminal = Self;
+        execute!(io::stdout(), Hide)?;
+        Ok(terminal)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        _ = execute!(io::stdout(), Show, Print("\r\n"));
+        _ = terminal::disable_raw_mode();
+    }
+}
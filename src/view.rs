@@ -0,0 +1,67 @@
+//! What widgets show, independent of where it is drawn
+//!
+//! Widgets and screens only describe what they show as a [`View`]. The [`ControllerUi`] lays
+//! each view out for the controller with a [`ControllerRenderer`] and hands it to any other
+//! [`Renderer`] added with [`ControllerUi::with_renderer`] or
+//! [`ControllerSelectBuilder::renderer`], such as the [graphics renderer](crate::graphics) for
+//! the brain's screen.
+//!
+//! [`ControllerUi`]: crate::widgets::ControllerUi
+//! [`ControllerRenderer`]: crate::display::ControllerRenderer
+//! [`ControllerUi::with_renderer`]: crate::widgets::ControllerUi::with_renderer
+//! [`ControllerSelectBuilder::renderer`]: crate::ControllerSelectBuilder::renderer
+
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+/// A description of what is on screen, independent of how it is drawn.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum View {
+    /// Nothing is shown.
+    Blank,
+    /// A list of options with one highlighted.
+    List {
+        title: String,
+        /// Short text shown at the right of the title, such as "3/7".
+        status: Option<String>,
+        options: Vec<String>,
+        selected: usize,
+    },
+    /// A value being adjusted, such as a number or an on/off setting.
+    Value {
+        title: String,
+        value: String,
+        can_decrease: bool,
+        can_increase: bool,
+    },
+    /// Up to three lines of information.
+    Dialog {
+        title: String,
+        description: String,
+        supporting: Option<String>,
+    },
+}
+
+impl View {
+    /// Creates a dialog showing a single line without a title, such as a countdown.
+    pub fn message(text: impl Into<String>) -> Self {
+        View::Dialog {
+            title: String::new(),
+            description: text.into(),
+            supporting: None,
+        }
+    }
+}
+
+/// Draws [`View`]s.
+pub trait Renderer {
+    /// Draws `view` in place of whatever was drawn before.
+    ///
+    /// This is only called when the view changes.
+    fn render(&mut self, view: &View);
+}
+
+impl<T: Renderer + ?Sized> Renderer for Box<T> {
+    fn render(&mut self, view: &View) {
+        (**self).render(view);
+    }
+}
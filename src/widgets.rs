@@ -0,0 +1,778 @@
+//! Async widgets for building menus on the controller screen
+//!
+//! Every widget is shown through a [`ControllerUi`], which owns the controller, the
+//! [inputs](crate::input) and the rate limiting of screen writes. Widgets return `None` when the
+//! driver backs out or the UI is cancelled, so they can be chained into menus with `?`-style
+//! early returns.
+//!
+//! ```ignore
+//! let ui = ControllerUi::new(controller, is_selecting);
+//! if Confirm::new("Reset odometry?").show(&ui).await == Some(true) {
+//!     // ...
+//! }
+//! ```
+
+use alloc::{
+    boxed::Box,
+    collections::VecDeque,
+    format,
+    rc::Rc,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::{
+    cell::{Cell, RefCell},
+    time::Duration,
+};
+
+use crate::{
+    display::ControllerRenderer,
+    input::{ControllerInput, InputSource},
+    keymap::{Action, Keymap},
+    platform::{sleep, ControllerState, Instant},
+    shared::SharedController,
+    view::{Renderer, View},
+};
+
+/// The shortest time between two writes that the controller reliably accepts.
+pub(crate) const SAFE_UPDATE_DURATION: Duration = Duration::from_millis(200);
+
+/// How often widgets read input and redraw the controller screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    pub poll: Duration,
+    pub redraw: Duration,
+}
+
+impl Timing {
+    /// The shortest redraw interval the controller can keep up with, since writes are spaced
+    /// this far apart anyway.
+    pub const MIN_REDRAW: Duration = SAFE_UPDATE_DURATION;
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Self {
+            poll: Duration::from_millis(20),
+            redraw: Self::MIN_REDRAW,
+        }
+    }
+}
+
+/// What the controller's screen shows, and what it should show.
+struct Screen {
+    renderer: ControllerRenderer,
+    /// The lines known to be on the screen, or `None` where a line needs writing
+    written: [Option<String>; 3],
+    /// When every line was last written
+    last_refresh: Instant,
+}
+
+/// Shared state for showing widgets on a controller.
+///
+/// Widgets describe what they show as a [`View`], which is laid out for the controller by a
+/// [`ControllerRenderer`] and handed to any other [renderers](crate::view). The controller only
+/// accepts one screen write every so often, so all writes go through this type, which spaces
+/// them out regardless of which widget makes them. Reads and writes are skipped and retried
+/// while other code is using the controller.
+pub struct ControllerUi {
+    controller: Rc<dyn SharedController>,
+    is_selecting: Rc<RefCell<bool>>,
+    keymap: Keymap,
+    timing: Timing,
+    last_write: Cell<Option<Instant>>,
+    /// The controller's input followed by any others
+    inputs: RefCell<Vec<Box<dyn InputSource>>>,
+    /// Actions polled from the inputs which haven't been handled yet
+    pending: RefCell<VecDeque<Action>>,
+    screen: RefCell<Screen>,
+    renderers: RefCell<Vec<Box<dyn Renderer>>>,
+    /// The view last handed to the renderers
+    view: RefCell<View>,
+}
+
+impl ControllerUi {
+    /// Creates a UI on a controller.
+    ///
+    /// Widgets are cancelled whenever `is_selecting` is set to `false`, and set it to `false`
+    /// themselves if the driver presses the exit button.
+    pub fn new(
+        controller: impl SharedController + 'static,
+        is_selecting: Rc<RefCell<bool>>,
+    ) -> Self {
+        Self::from_shared(Rc::new(controller), is_selecting)
+    }
+
+    pub(crate) fn from_shared(
+        controller: Rc<dyn SharedController>,
+        is_selecting: Rc<RefCell<bool>>,
+    ) -> Self {
+        let keymap = Keymap::default();
+        Self {
+            inputs: RefCell::new(vec![Box::new(ControllerInput::new(
+                controller.clone(),
+                keymap.clone(),
+            ))]),
+            controller,
+            is_selecting,
+            keymap,
+            timing: Timing::default(),
+            last_write: Cell::new(None),
+            pending: RefCell::new(VecDeque::new()),
+            screen: RefCell::new(Screen {
+                renderer: ControllerRenderer::new(),
+                written: [None, None, None],
+                last_refresh: Instant::now(),
+            }),
+            renderers: RefCell::new(Vec::new()),
+            view: RefCell::new(View::Blank),
+        }
+    }
+
+    /// Sets the buttons used to navigate widgets.
+    pub fn with_keymap(mut self, keymap: Keymap) -> Self {
+        self.inputs.get_mut()[0] = Box::new(ControllerInput::new(
+            self.controller.clone(),
+            keymap.clone(),
+        ));
+        self.keymap = keymap;
+        self
+    }
+
+    /// Adds another input which navigates widgets alongside the controller, such as the
+    /// [brain's touchscreen](crate::input::Touchscreen).
+    pub fn with_input(mut self, input: impl InputSource + 'static) -> Self {
+        self.inputs.get_mut().push(Box::new(input));
+        self
+    }
+
+    /// Sets how often widgets read input and redraw.
+    pub fn with_timing(mut self, timing: Timing) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    /// Adds a renderer which draws every widget alongside the controller, such as on the
+    /// [brain's screen](crate::graphics).
+    pub fn with_renderer(mut self, renderer: impl Renderer + 'static) -> Self {
+        self.renderers.get_mut().push(Box::new(renderer));
+        self
+    }
+
+    /// Shows `view` on the controller and hands it to the other renderers if it differs from
+    /// the last one.
+    ///
+    /// The controller's lines are written one at a time as it becomes ready for them, so this
+    /// writes at most one line. Call it again, or [`update_screen`](Self::update_screen), to
+    /// write the rest, or use [`draw`](Self::draw) to wait until they are all written.
+    pub fn show_view(&self, view: View) {
+        if let (Ok(mut shown), Ok(mut screen), Ok(mut renderers)) = (
+            self.view.try_borrow_mut(),
+            self.screen.try_borrow_mut(),
+            self.renderers.try_borrow_mut(),
+        ) {
+            if *shown != view {
+                screen.renderer.render(&view);
+                for renderer in renderers.iter_mut() {
+                    renderer.render(&view);
+                }
+                *shown = view;
+            }
+        }
+        self.update_screen();
+    }
+
+    /// Shows `view` and waits until the controller's screen has been written.
+    pub async fn draw(&self, view: View) {
+        self.show_view(view);
+        while !self.update_screen() {
+            sleep(self.timing.poll).await;
+        }
+    }
+
+    /// Writes the next line of the view on the controller if it is ready for a write, returning
+    /// `true` once the screen shows the whole view.
+    ///
+    /// Lines are rewritten a redraw interval after they were last all written, in case the
+    /// controller dropped one.
+    pub fn update_screen(&self) -> bool {
+        let Ok(mut screen) = self.screen.try_borrow_mut() else {
+            return false;
+        };
+        let complete = screen.written.iter().all(Option::is_some);
+        if complete && screen.last_refresh.elapsed() >= self.timing.redraw {
+            screen.written = [None, None, None];
+        }
+        let lines = screen.renderer.lines().clone();
+        let Some(i) = (0..lines.len()).find(|&i| screen.written[i].as_ref() != Some(&lines[i]))
+        else {
+            return true;
+        };
+        if !self.can_write() {
+            return false;
+        }
+        if lines.iter().all(String::is_empty) {
+            // One write clears every line
+            let cleared = self.controller.try_with(&mut |controller| {
+                _ = controller.screen.try_clear_screen();
+            });
+            if !cleared {
+                return false;
+            }
+            self.last_write.set(Some(Instant::now()));
+            screen.written = lines.clone().map(Some);
+        } else if self.write_now(&lines[i], i as u8 + 1, 1) {
+            screen.written[i] = Some(lines[i].clone());
+        } else {
+            return false;
+        }
+        if screen.written.iter().all(Option::is_some) {
+            screen.last_refresh = Instant::now();
+        }
+        screen
+            .written
+            .iter()
+            .zip(&lines)
+            .all(|(written, line)| written.as_ref() == Some(line))
+    }
+
+    pub const fn keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    pub const fn timing(&self) -> Timing {
+        self.timing
+    }
+
+    /// Returns `true` unless the UI has been cancelled.
+    ///
+    /// If the flag is being written elsewhere at this moment, the UI counts as active.
+    pub fn is_active(&self) -> bool {
+        self.is_selecting
+            .try_borrow()
+            .map_or(true, |active| *active)
+    }
+
+    /// Sets whether the UI is active. Any widget being shown is cancelled when this is `false`.
+    pub fn set_active(&self, active: bool) {
+        if let Ok(mut is_selecting) = self.is_selecting.try_borrow_mut() {
+            *is_selecting = active;
+        }
+    }
+
+    /// Reads the current state of the controller's buttons and joysticks, or returns `None` if
+    /// the controller is in use elsewhere.
+    pub fn controller_state(&self) -> Option<ControllerState> {
+        let mut state = None;
+        self.controller
+            .try_with(&mut |controller| state = Some(controller.state().unwrap_or_default()));
+        state
+    }
+
+    /// Polls every input, returning the next action any of them produced.
+    pub(crate) fn next_action(&self) -> Option<Action> {
+        let mut pending = self.pending.borrow_mut();
+        for input in self.inputs.borrow_mut().iter_mut() {
+            pending.extend(input.poll());
+        }
+        pending.pop_front()
+    }
+
+    /// Drops unhandled actions and ignores inputs which are held right now until they are
+    /// released.
+    pub(crate) fn reset_inputs(&self) {
+        self.pending.borrow_mut().clear();
+        for input in self.inputs.borrow_mut().iter_mut() {
+            input.reset();
+        }
+    }
+
+    fn can_write(&self) -> bool {
+        self.last_write
+            .get()
+            .is_none_or(|last| last.elapsed() >= SAFE_UPDATE_DURATION)
+    }
+
+    /// Waits until the controller can accept another write.
+    async fn wait_for_write(&self) {
+        if let Some(last) = self.last_write.get() {
+            let elapsed = last.elapsed();
+            if elapsed < SAFE_UPDATE_DURATION {
+                sleep(SAFE_UPDATE_DURATION - elapsed).await;
+            }
+        }
+    }
+
+    /// Reads the controller's battery level from 0 to 1, or returns `None` if it is
+    /// disconnected or in use elsewhere.
+    pub fn controller_battery(&self) -> Option<f64> {
+        let mut level = None;
+        self.controller
+            .try_with(&mut |controller| level = controller.battery_capacity().ok());
+        level
+    }
+
+    /// Rumbles the controller if it is ready for another write, returning `false` if it was
+    /// skipped.
+    ///
+    /// The pattern uses `.` for short pulses, `-` for long pulses and spaces for pauses.
+    pub fn try_rumble(&self, pattern: &str) -> bool {
+        if !self.can_write() {
+            return false;
+        }
+        let rumbled = self.controller.try_with(&mut |controller| {
+            _ = controller.try_rumble(pattern);
+        });
+        if rumbled {
+            self.last_write.set(Some(Instant::now()));
+        }
+        rumbled
+    }
+
+    /// Writes text immediately, returning `false` if the controller was in use elsewhere.
+    fn write_now(&self, text: &str, line: u8, column: u8) -> bool {
+        let written = self.controller.try_with(&mut |controller| {
+            _ = controller.screen.try_set_text(text, line, column);
+        });
+        if written {
+            self.last_write.set(Some(Instant::now()));
+        }
+        written
+    }
+
+    /// Forgets what a line of the screen shows after it was written directly, so the next view
+    /// rewrites it.
+    fn forget_line(&self, line: u8) {
+        if let Ok(mut screen) = self.screen.try_borrow_mut() {
+            if let Some(written) = screen.written.get_mut(usize::from(line).wrapping_sub(1)) {
+                *written = None;
+            }
+        }
+    }
+
+    /// Writes text to a line of the screen once the controller is ready for it.
+    ///
+    /// This writes around the views, which is only useful for drawing something a [`View`]
+    /// can't describe. The line is rewritten with the view the next time one is shown.
+    pub async fn set_text(&self, text: impl AsRef<str>, line: u8, column: u8) {
+        loop {
+            self.wait_for_write().await;
+            if self.write_now(text.as_ref(), line, column) {
+                break;
+            }
+            sleep(self.timing.poll).await;
+        }
+        self.forget_line(line);
+    }
+
+    /// Writes text to a line of the screen if the controller is ready for it, returning `false`
+    /// if the write was skipped.
+    ///
+    /// Like [`set_text`](Self::set_text), this writes around the views.
+    pub fn try_set_text(&self, text: impl AsRef<str>, line: u8, column: u8) -> bool {
+        let written = self.can_write() && self.write_now(text.as_ref(), line, column);
+        if written {
+            self.forget_line(line);
+        }
+        written
+    }
+
+    /// Clears the screen once the controller is ready for it.
+    pub async fn clear(&self) {
+        self.draw(View::Blank).await;
+    }
+
+    /// Runs a widget until it produces a value or is backed out of.
+    ///
+    /// The widget's view is shown with `prompt` as its title, and written to the controller one
+    /// line at a time so input is read between writes.
+    pub(crate) async fn interact<W: Interaction>(
+        &self,
+        prompt: &str,
+        widget: &mut W,
+    ) -> Option<W::Output> {
+        self.reset_inputs();
+        self.clear().await;
+        let result = loop {
+            match self.next_action() {
+                Some(Action::Back) => break None,
+                Some(Action::Exit) => {
+                    self.set_active(false);
+                    break None;
+                }
+                Some(action) => {
+                    if let Some(output) = widget.handle(action) {
+                        break Some(output);
+                    }
+                }
+                None => {}
+            }
+            if !self.is_active() {
+                break None;
+            }
+            self.show_view(widget.view(prompt));
+            sleep(self.timing.poll).await;
+        };
+        self.clear().await;
+        result
+    }
+}
+
+/// A widget driven by [`ControllerUi::interact`].
+pub(crate) trait Interaction {
+    type Output;
+
+    /// Handles a navigation action, returning a value once the widget is done.
+    ///
+    /// [`Action::Back`] and [`Action::Exit`] are handled by the UI and never passed here.
+    fn handle(&mut self, action: Action) -> Option<Self::Output>;
+
+    /// Describes what the widget shows, with `title` at the top.
+    fn view(&self, title: &str) -> View;
+}
+
+/// Returns the index that [`Action::NextGroup`] or [`Action::PreviousGroup`] jumps to from
+/// `selected`, given the sorted indices where each group starts.
+fn group_jump(group_starts: &[usize], selected: usize, forward: bool) -> usize {
+    let current = group_starts
+        .iter()
+        .rposition(|&start| start <= selected)
+        .unwrap_or(0);
+    let target = if forward {
+        (current + 1) % group_starts.len()
+    } else {
+        (current + group_starts.len() - 1) % group_starts.len()
+    };
+    group_starts[target]
+}
+
+/// Lets the driver pick one of several options from a sideways-scrolling list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Picker {
+    prompt: String,
+    options: Vec<String>,
+    group_starts: Vec<usize>,
+    selected: usize,
+    show_position: bool,
+}
+
+impl Picker {
+    /// Creates a picker with at least one option.
+    pub fn new(prompt: impl Into<String>, options: Vec<String>) -> Self {
+        assert!(!options.is_empty(), "Picker requires at least one option.");
+        Self {
+            prompt: prompt.into(),
+            options,
+            group_starts: Vec::new(),
+            selected: 0,
+            show_position: false,
+        }
+    }
+
+    /// Sets the sorted indices where groups of options begin, which the group actions jump
+    /// between.
+    pub fn groups(mut self, group_starts: Vec<usize>) -> Self {
+        self.group_starts = group_starts;
+        self
+    }
+
+    /// Shows the position of the highlighted option, such as "3/7", at the right of the header.
+    pub fn position_indicator(mut self, show: bool) -> Self {
+        self.show_position = show;
+        self
+    }
+
+    /// Sets the option highlighted when the picker opens.
+    pub fn selected(mut self, index: usize) -> Self {
+        self.selected = index.min(self.options.len() - 1);
+        self
+    }
+
+    /// Returns the index of the highlighted option.
+    pub fn cursor(&self) -> usize {
+        self.selected
+    }
+
+    /// Returns the position shown at the right of the header, if it is shown.
+    fn status(&self) -> Option<String> {
+        self.show_position
+            .then(|| format!("{}/{}", self.selected + 1, self.options.len()))
+    }
+
+    /// Shows the picker, returning the index of the chosen option.
+    pub async fn show(mut self, ui: &ControllerUi) -> Option<usize> {
+        self.pick(ui).await
+    }
+
+    /// Shows the picker without consuming it, so that [`cursor`](Self::cursor) can tell where
+    /// the driver left off even if they backed out.
+    pub async fn pick(&mut self, ui: &ControllerUi) -> Option<usize> {
+        let prompt = self.prompt.clone();
+        ui.interact(&prompt, self).await
+    }
+}
+
+impl Interaction for Picker {
+    type Output = usize;
+
+    fn handle(&mut self, action: Action) -> Option<usize> {
+        let len = self.options.len();
+        match action {
+            Action::Previous => self.selected = (self.selected + len - 1) % len,
+            Action::Next => self.selected = (self.selected + 1) % len,
+            Action::NextGroup if !self.group_starts.is_empty() => {
+                self.selected = group_jump(&self.group_starts, self.selected, true);
+            }
+            Action::PreviousGroup if !self.group_starts.is_empty() => {
+                self.selected = group_jump(&self.group_starts, self.selected, false);
+            }
+            Action::Jump(index) if index < len => self.selected = index,
+            Action::Confirm => return Some(self.selected),
+            _ => {}
+        }
+        None
+    }
+
+    fn view(&self, title: &str) -> View {
+        View::List {
+            title: title.to_string(),
+            status: self.status(),
+            options: self.options.clone(),
+            selected: self.selected,
+        }
+    }
+}
+
+/// Asks the driver a yes or no question.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Confirm {
+    picker: Picker,
+}
+
+impl Confirm {
+    /// Creates a confirmation with "No" highlighted.
+    pub fn new(prompt: impl Into<String>) -> Self {
+        Self::with_labels(prompt, "No", "Yes")
+    }
+
+    /// Creates a confirmation with custom labels for the two answers.
+    pub fn with_labels(
+        prompt: impl Into<String>,
+        no: impl Into<String>,
+        yes: impl Into<String>,
+    ) -> Self {
+        Self {
+            picker: Picker::new(prompt, vec![no.into(), yes.into()]),
+        }
+    }
+
+    /// Shows the confirmation, returning `true` if the driver picked yes.
+    pub async fn show(self, ui: &ControllerUi) -> Option<bool> {
+        self.picker.show(ui).await.map(|selected| selected == 1)
+    }
+}
+
+/// Lets the driver switch a setting on or off.
+///
+/// Jumping to 0 switches it off and jumping to anything else switches it on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toggle {
+    prompt: String,
+    off: String,
+    on: String,
+    value: bool,
+}
+
+impl Toggle {
+    /// Creates a toggle starting at `value`.
+    pub fn new(prompt: impl Into<String>, value: bool) -> Self {
+        Self {
+            prompt: prompt.into(),
+            off: "Off".to_string(),
+            on: "On".to_string(),
+            value,
+        }
+    }
+
+    /// Sets the labels shown for the two states.
+    pub fn labels(mut self, off: impl Into<String>, on: impl Into<String>) -> Self {
+        self.off = off.into();
+        self.on = on.into();
+        self
+    }
+
+    /// Shows the toggle, returning the chosen state.
+    pub async fn show(mut self, ui: &ControllerUi) -> Option<bool> {
+        let prompt = core::mem::take(&mut self.prompt);
+        ui.interact(&prompt, &mut self).await
+    }
+}
+
+impl Interaction for Toggle {
+    type Output = bool;
+
+    fn handle(&mut self, action: Action) -> Option<bool> {
+        match action {
+            Action::Next | Action::Previous => self.value = !self.value,
+            Action::Jump(index) => self.value = index != 0,
+            Action::Confirm => return Some(self.value),
+            _ => {}
+        }
+        None
+    }
+
+    fn view(&self, title: &str) -> View {
+        View::Value {
+            title: title.to_string(),
+            value: if self.value { &self.on } else { &self.off }.clone(),
+            can_decrease: true,
+            can_increase: true,
+        }
+    }
+}
+
+/// Lets the driver dial in a number between two bounds.
+///
+/// Next and previous change the value by one step, and the group actions by ten steps. Jumping
+/// to `n` sets the value `n` steps above the minimum.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spinner {
+    prompt: String,
+    value: f64,
+    min: f64,
+    max: f64,
+    step: f64,
+    decimals: usize,
+    suffix: String,
+}
+
+impl Spinner {
+    /// Creates a spinner starting at `min`.
+    pub fn new(prompt: impl Into<String>, min: f64, max: f64, step: f64) -> Self {
+        assert!(min <= max, "Spinner minimum must not exceed its maximum.");
+        Self {
+            prompt: prompt.into(),
+            value: min,
+            min,
+            max,
+            step,
+            decimals: 0,
+            suffix: String::new(),
+        }
+    }
+
+    /// Sets the starting value.
+    pub fn value(mut self, value: f64) -> Self {
+        self.value = value.clamp(self.min, self.max);
+        self
+    }
+
+    /// Sets how many decimal places are shown.
+    pub fn decimals(mut self, decimals: usize) -> Self {
+        self.decimals = decimals;
+        self
+    }
+
+    /// Sets a unit shown after the value, such as `"s"`.
+    pub fn suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.suffix = suffix.into();
+        self
+    }
+
+    /// Shows the spinner, returning the chosen value.
+    pub async fn show(mut self, ui: &ControllerUi) -> Option<f64> {
+        let prompt = core::mem::take(&mut self.prompt);
+        ui.interact(&prompt, &mut self).await
+    }
+
+    fn change(&mut self, steps: f64) {
+        self.value = (self.value + self.step * steps).clamp(self.min, self.max);
+    }
+}
+
+impl Interaction for Spinner {
+    type Output = f64;
+
+    fn handle(&mut self, action: Action) -> Option<f64> {
+        match action {
+            Action::Next => self.change(1.0),
+            Action::Previous => self.change(-1.0),
+            Action::NextGroup => self.change(10.0),
+            Action::PreviousGroup => self.change(-10.0),
+            Action::Jump(steps) => {
+                self.value = self.min;
+                self.change(steps as f64);
+            }
+            Action::Confirm => return Some(self.value),
+            _ => {}
+        }
+        None
+    }
+
+    fn view(&self, title: &str) -> View {
+        View::Value {
+            title: title.to_string(),
+            value: format!("{:.*}{}", self.decimals, self.value, self.suffix),
+            can_decrease: self.value > self.min,
+            can_increase: self.value < self.max,
+        }
+    }
+}
+
+/// Shows up to three lines of information.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dialog {
+    title: String,
+    description: String,
+    supporting: Option<String>,
+}
+
+impl Dialog {
+    pub fn new(title: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            description: description.into(),
+            supporting: None,
+        }
+    }
+
+    /// Sets a third line shown below the description.
+    pub fn supporting(mut self, supporting: impl Into<String>) -> Self {
+        self.supporting = Some(supporting.into());
+        self
+    }
+
+    /// Draws the dialog once without waiting for input.
+    pub async fn draw(&self, ui: &ControllerUi) {
+        ui.draw(self.view()).await;
+    }
+
+    fn view(&self) -> View {
+        View::Dialog {
+            title: self.title.clone(),
+            description: self.description.clone(),
+            supporting: self.supporting.clone(),
+        }
+    }
+
+    /// Shows the dialog until the driver confirms or backs out of it.
+    pub async fn show(self, ui: &ControllerUi) -> Option<()> {
+        let title = self.title.clone();
+        ui.interact(&title, &mut DialogInteraction(self)).await
+    }
+}
+
+struct DialogInteraction(Dialog);
+
+impl Interaction for DialogInteraction {
+    type Output = ();
+
+    fn handle(&mut self, action: Action) -> Option<()> {
+        (action == Action::Confirm).then_some(())
+    }
+
+    fn view(&self, _title: &str) -> View {
+        self.0.view()
+    }
+}
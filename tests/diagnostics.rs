@@ -0,0 +1,116 @@
+//! Tests for the device health scan, run on the emulator with `cargo host-test`.
+
+#![cfg(not(target_vendor = "vex"))]
+
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use autons_controller::{
+    diagnostics::{scan_devices, show_device_health, DeviceProblem, ExpectedDevice},
+    emulator::{self, Controller, SmartDeviceType, TAP},
+    widgets::ControllerUi,
+    Button,
+};
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+fn expected() -> Vec<ExpectedDevice> {
+    vec![
+        ExpectedDevice::new(1, SmartDeviceType::Motor, "Left drive"),
+        ExpectedDevice::new(2, SmartDeviceType::Motor, "Intake"),
+        ExpectedDevice::new(3, SmartDeviceType::Imu, "IMU"),
+        ExpectedDevice::new(21, SmartDeviceType::Motor, "Arm"),
+    ]
+}
+
+#[test]
+fn scan_finds_missing_mismatched_and_hot_devices() {
+    emulator::plug_device(1, Some(SmartDeviceType::Motor));
+    emulator::plug_device(3, Some(SmartDeviceType::Rotation));
+    emulator::plug_device(21, Some(SmartDeviceType::Motor));
+    emulator::set_motor_overheating(21, Some(55.0));
+
+    let issues = scan_devices(&expected());
+    let problems = issues
+        .iter()
+        .map(|issue| (issue.device.port(), issue.problem))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        problems,
+        [
+            (2, DeviceProblem::Missing),
+            (
+                3,
+                DeviceProblem::Mismatched {
+                    actual: SmartDeviceType::Rotation
+                }
+            ),
+            (21, DeviceProblem::Overheating { temperature: 55.0 }),
+        ]
+    );
+    let summaries = issues
+        .iter()
+        .map(|issue| issue.summary())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summaries,
+        ["P2 Intake missing", "P3 IMU is Rotation", "P21 Arm 55C"]
+    );
+
+    // Everything plugged in and cool
+    emulator::plug_device(2, Some(SmartDeviceType::Motor));
+    emulator::plug_device(3, Some(SmartDeviceType::Imu));
+    emulator::set_motor_overheating(21, None);
+    assert!(scan_devices(&expected()).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Smart port must be from 1 to 21.")]
+fn port_zero_is_rejected() {
+    ExpectedDevice::new(0, SmartDeviceType::Motor, "Left drive");
+}
+
+#[test]
+fn health_screen_pages_through_issues() {
+    emulator::plug_device(1, Some(SmartDeviceType::Motor));
+    emulator::plug_device(3, Some(SmartDeviceType::Imu));
+    emulator::plug_device(21, Some(SmartDeviceType::Motor));
+    let controller = Rc::new(RefCell::new(Controller::new()));
+    controller
+        .borrow_mut()
+        .press(Button::Right, ms(1000), TAP)
+        .press(Button::A, ms(2000), TAP);
+    let ui = ControllerUi::new(controller.clone(), Rc::new(RefCell::new(true)));
+
+    let expected = [
+        ExpectedDevice::new(2, SmartDeviceType::Motor, "Intake"),
+        ExpectedDevice::new(3, SmartDeviceType::Motor, "Arm"),
+    ];
+    emulator::run_for(show_device_health(&ui, &expected), ms(3000)).unwrap();
+
+    let screen = &controller.borrow().screen;
+    let first = screen.frame_at(ms(900));
+    assert!(first.contains("P2 Intake missing"), "{first}");
+    assert!(first.contains("< 1/2 >"), "{first}");
+    let second = screen.frame_at(ms(1900));
+    assert!(second.contains("P3 Arm is Imu"), "{second}");
+    assert!(second.contains("< 2/2 >"), "{second}");
+    screen.assert_rate_limited();
+}
+
+#[test]
+fn health_screen_says_when_every_device_is_fine() {
+    emulator::plug_device(2, Some(SmartDeviceType::Motor));
+    let controller = Rc::new(RefCell::new(Controller::new()));
+    controller.borrow_mut().press(Button::A, ms(1000), TAP);
+    let ui = ControllerUi::new(controller.clone(), Rc::new(RefCell::new(true)));
+
+    let expected = [ExpectedDevice::new(2, SmartDeviceType::Motor, "Intake")];
+    emulator::run_for(show_device_health(&ui, &expected), ms(2000)).unwrap();
+    assert!(controller
+        .borrow()
+        .screen
+        .frame_at(ms(900))
+        .contains("All devices OK"));
+}
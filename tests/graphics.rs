@@ -0,0 +1,207 @@
+//! Reference image tests for the graphics renderer, run with `cargo host-test`.
+//!
+//! Views are drawn into an in-memory framebuffer and compared against the reference images in
+//! `tests/snapshots`, stored as text with one character per pixel. Run with `UPDATE_SNAPSHOTS=1`
+//! to write new images after an intended change to a layout.
+
+#![cfg(all(not(target_vendor = "vex"), feature = "graphics"))]
+
+use std::{cell::RefCell, convert::Infallible, fs, path::PathBuf, rc::Rc, time::Duration};
+
+use autons_controller::{
+    emulator::{self, Controller, TAP},
+    graphics::{GraphicsRenderer, Theme},
+    view::{Renderer, View},
+    widgets::{ControllerUi, Picker},
+    Button,
+};
+use embedded_graphics::{mono_font::ascii::FONT_5X8, pixelcolor::Rgb888, prelude::*};
+
+const WIDTH: usize = 120;
+const HEIGHT: usize = 64;
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+/// A small screen which clones share, so it can be read while a renderer owns it.
+#[derive(Clone)]
+struct Framebuffer(Rc<RefCell<Vec<Rgb888>>>);
+
+impl Framebuffer {
+    fn new() -> Self {
+        Self(Rc::new(RefCell::new(vec![Rgb888::BLACK; WIDTH * HEIGHT])))
+    }
+
+    /// Writes the framebuffer as text, with a character for each color of the theme.
+    fn image(&self) -> String {
+        let theme = theme();
+        let palette = [
+            (theme.background, ' '),
+            (theme.text, '#'),
+            (theme.header, '='),
+            (theme.highlight, '+'),
+            (theme.dim, ':'),
+        ];
+        self.0
+            .borrow()
+            .chunks(WIDTH)
+            .map(|row| {
+                let mut line = row
+                    .iter()
+                    .map(|pixel| {
+                        palette
+                            .iter()
+                            .find(|(color, _)| color == pixel)
+                            .map_or('?', |(_, c)| *c)
+                    })
+                    .collect::<String>();
+                line.push('\n');
+                line
+            })
+            .collect()
+    }
+}
+
+impl OriginDimensions for Framebuffer {
+    fn size(&self) -> Size {
+        Size::new(WIDTH as u32, HEIGHT as u32)
+    }
+}
+
+impl DrawTarget for Framebuffer {
+    type Color = Rgb888;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let mut buffer = self.0.borrow_mut();
+        for Pixel(point, color) in pixels {
+            if (0..WIDTH as i32).contains(&point.x) && (0..HEIGHT as i32).contains(&point.y) {
+                buffer[point.y as usize * WIDTH + point.x as usize] = color;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The default theme with a font small enough for the test screen. White text on the header
+/// and highlight is drawn the same as other text.
+fn theme() -> Theme {
+    Theme {
+        font: &FONT_5X8,
+        ..Theme::default()
+    }
+}
+
+fn renderer() -> (Framebuffer, GraphicsRenderer<Framebuffer>) {
+    let framebuffer = Framebuffer::new();
+    let renderer = GraphicsRenderer::new(framebuffer.clone()).with_theme(theme());
+    (framebuffer, renderer)
+}
+
+/// Compares `image` against the reference image called `name`, writing it instead if it is
+/// missing or `UPDATE_SNAPSHOTS` is set.
+#[track_caller]
+fn assert_image(name: &str, image: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("graphics_{name}.txt"));
+    match fs::read_to_string(&path) {
+        Ok(reference) if std::env::var_os("UPDATE_SNAPSHOTS").is_none() => {
+            assert!(image == reference, "image `{name}` doesn't match:\n{image}");
+        }
+        _ => fs::write(&path, image).unwrap(),
+    }
+}
+
+fn options(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn draws_a_list() {
+    let (framebuffer, mut renderer) = renderer();
+    renderer.render(&View::List {
+        title: "Pick route".into(),
+        status: Some("2/3".into()),
+        options: options(&["Left", "Right", "Skills"]),
+        selected: 1,
+    });
+    assert_image("list", &framebuffer.image());
+}
+
+#[test]
+fn scrolls_long_lists() {
+    let (framebuffer, mut renderer) = renderer();
+    let list = |selected| View::List {
+        title: "A title far too long for the header".into(),
+        status: None,
+        options: options(&[
+            "One", "Two", "Three", "Four", "Five", "Six", "Seven", "Eight",
+        ]),
+        selected,
+    };
+    renderer.render(&list(6));
+    assert_image("list_scrolled", &framebuffer.image());
+
+    // Moving back up within the visible rows doesn't scroll
+    renderer.render(&list(4));
+    assert_image("list_scrolled_up", &framebuffer.image());
+}
+
+#[test]
+fn draws_a_value() {
+    let (framebuffer, mut renderer) = renderer();
+    renderer.render(&View::Value {
+        title: "Start delay".into(),
+        value: "1.5s".into(),
+        can_decrease: true,
+        can_increase: false,
+    });
+    assert_image("value", &framebuffer.image());
+}
+
+#[test]
+fn draws_a_dialog() {
+    let (framebuffer, mut renderer) = renderer();
+    renderer.render(&View::Dialog {
+        title: "Selected".into(),
+        description: "Match / Right".into(),
+        supporting: Some("Good luck!".into()),
+    });
+    assert_image("dialog", &framebuffer.image());
+
+    renderer.render(&View::message("Go!"));
+    assert_image("message", &framebuffer.image());
+}
+
+#[test]
+fn follows_a_picker() {
+    let controller = Rc::new(RefCell::new(Controller::new()));
+    let (framebuffer, renderer) = renderer();
+    let ui =
+        ControllerUi::new(controller.clone(), Rc::new(RefCell::new(true))).with_renderer(renderer);
+    controller
+        .borrow_mut()
+        .press(Button::Right, ms(500), TAP)
+        .press(Button::A, ms(2000), TAP);
+    let picker = Picker::new("Route", options(&["Left", "Right", "Skills"]));
+
+    let mut picker = Box::pin(picker.show(&ui));
+    assert_eq!(emulator::run_for(picker.as_mut(), ms(1000)), None);
+    let mut expected = GraphicsRenderer::new(Framebuffer::new()).with_theme(theme());
+    expected.render(&View::List {
+        title: "Route".into(),
+        status: None,
+        options: options(&["Left", "Right", "Skills"]),
+        selected: 1,
+    });
+    assert_eq!(framebuffer.image(), expected.target().image());
+
+    assert_eq!(emulator::run_for(picker, ms(2000)), Some(Some(1)));
+    // The display is cleared along with the controller
+    assert!(framebuffer.image().chars().all(|c| c == ' ' || c == '\n'));
+}
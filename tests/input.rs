@@ -0,0 +1,204 @@
+//! Tests for the input sources, run on the emulator with `cargo host-test`.
+
+#![cfg(not(target_vendor = "vex"))]
+
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use autons_controller::{
+    emulator::{self, Controller, Instant, TAP},
+    input::{Buttons, Events, Touchscreen},
+    widgets::{ControllerUi, Picker},
+    Action, Binding, Button, Keymap, Stick,
+};
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+fn ui() -> ControllerUi {
+    ControllerUi::new(
+        Rc::new(RefCell::new(Controller::new())),
+        Rc::new(RefCell::new(true)),
+    )
+}
+
+/// Returns a UI reading the keymap from a controller scripted by `script`.
+fn keymap_ui(keymap: Keymap, script: impl FnOnce(&mut Controller)) -> ControllerUi {
+    let mut controller = Controller::new();
+    script(&mut controller);
+    ControllerUi::new(
+        Rc::new(RefCell::new(controller)),
+        Rc::new(RefCell::new(true)),
+    )
+    .with_keymap(keymap)
+}
+
+fn picker() -> Picker {
+    Picker::new(
+        "Route",
+        ["Left", "Right", "Skills"].map(String::from).to_vec(),
+    )
+}
+
+/// Returns a reading which is on during each of the given ranges of milliseconds.
+fn pressed_during(ranges: &'static [(u64, u64)]) -> impl FnMut() -> Option<bool> {
+    move || {
+        let now = Instant::now().since_start();
+        Some(
+            ranges
+                .iter()
+                .any(|&(start, end)| (ms(start)..ms(end)).contains(&now)),
+        )
+    }
+}
+
+/// Returns a touchscreen touched at each `x` during the given ranges of milliseconds.
+fn touched_during(touches: &'static [(u64, u64, i16)]) -> Touchscreen {
+    Touchscreen::new(move || {
+        let now = Instant::now().since_start();
+        touches
+            .iter()
+            .find(|&&(start, end, _)| (ms(start)..ms(end)).contains(&now))
+            .map(|&(_, _, x)| (x, 100))
+    })
+}
+
+#[test]
+fn touchscreen_taps_columns() {
+    let ui = ui().with_input(touched_during(&[
+        (300, 400, 400),   // Next
+        (600, 700, 400),   // Next
+        (900, 1000, 50),   // Previous
+        (1200, 1300, 240), // Confirm
+    ]));
+    assert_eq!(
+        emulator::run_for(picker().show(&ui), ms(2000)),
+        Some(Some(1))
+    );
+}
+
+#[test]
+fn touchscreen_hold_backs_out() {
+    let ui = ui().with_input(touched_during(&[(300, 1500, 240)]));
+    assert_eq!(emulator::run_for(picker().show(&ui), ms(1200)), Some(None));
+}
+
+#[test]
+fn buttons_debounce_bouncing_contacts() {
+    let buttons = Buttons::new()
+        // Bounces for 20ms before settling
+        .button(
+            Action::Next,
+            pressed_during(&[(300, 305), (310, 315), (320, 500)]),
+        )
+        .button(Action::Confirm, pressed_during(&[(800, 900)]));
+    let ui = ui().with_input(buttons);
+    assert_eq!(
+        emulator::run_for(picker().show(&ui), ms(1500)),
+        Some(Some(1))
+    );
+}
+
+#[test]
+fn buttons_held_when_opened_wait_for_release() {
+    let buttons = Buttons::new()
+        .button(Action::Next, pressed_during(&[(0, 500), (700, 800)]))
+        .button(Action::Confirm, pressed_during(&[(1000, 1100)]));
+    let ui = ui().with_input(buttons);
+    // Let the buttons read as held before the picker opens
+    emulator::advance(ms(200));
+    assert_eq!(
+        emulator::run_for(picker().show(&ui), ms(1500)),
+        Some(Some(1))
+    );
+}
+
+#[test]
+fn events_jump_to_an_option() {
+    let events = Events::new();
+    let ui = ui().with_input(events.clone());
+    events.push(Action::Jump(2));
+    events.push(Action::Confirm);
+    assert_eq!(
+        emulator::run_for(picker().show(&ui), ms(1000)),
+        Some(Some(2))
+    );
+}
+
+#[test]
+fn chord_beats_its_single_buttons() {
+    let keymap = Keymap {
+        confirm: Binding::chord([Button::L1, Button::R1]),
+        ..Keymap::default()
+    };
+    let ui = keymap_ui(keymap, |controller| {
+        controller
+            .press(Button::Right, ms(300), TAP)
+            // L1 and R1 alone jump between groups
+            .press(Button::L1, ms(600), TAP)
+            .press(Button::R1, ms(600), TAP);
+    });
+    assert_eq!(
+        emulator::run_for(picker().show(&ui), ms(1000)),
+        Some(Some(1))
+    );
+}
+
+#[test]
+fn held_binding_fires_once() {
+    let keymap = Keymap {
+        next: Binding::new(Button::X).held(ms(300)),
+        repeat: None,
+        ..Keymap::default()
+    };
+    let ui = keymap_ui(keymap, |controller| {
+        controller
+            // Released before the hold is up
+            .press(Button::X, ms(300), TAP)
+            .press(Button::X, ms(600), ms(1500))
+            .press(Button::A, ms(2400), TAP);
+    });
+    assert_eq!(
+        emulator::run_for(picker().show(&ui), ms(3000)),
+        Some(Some(1))
+    );
+}
+
+#[test]
+fn held_navigation_repeats_faster_down_to_the_floor() {
+    let options = (0..200).map(|i| i.to_string()).collect();
+    let ui = keymap_ui(Keymap::default(), |controller| {
+        controller
+            .press(Button::Right, ms(300), ms(3000))
+            .press(Button::A, ms(3600), TAP);
+    });
+    // The first press, then repeats after 400ms at 160ms, 136ms, 116ms and so on until the
+    // interval settles at 40ms for the rest of the hold
+    assert_eq!(
+        emulator::run_for(Picker::new("Route", options).show(&ui), ms(4000)),
+        Some(Some(53))
+    );
+}
+
+#[test]
+fn stick_flicks_past_the_threshold() {
+    let keymap = Keymap {
+        repeat: None,
+        ..Keymap::default()
+    };
+    let ui = keymap_ui(keymap, |controller| {
+        controller
+            // Short of the 0.7 threshold
+            .tilt(Stick::Left, 0.65, ms(300), ms(200))
+            .tilt(Stick::Left, 0.75, ms(700), ms(200))
+            // Still above half the threshold, so coming back up isn't a second flick
+            .tilt(Stick::Left, 0.5, ms(900), ms(200))
+            .tilt(Stick::Left, 0.75, ms(1100), ms(200))
+            .tilt(Stick::Left, 0.75, ms(1500), ms(200))
+            .press(Button::A, ms(2000), TAP);
+    });
+    assert_eq!(
+        emulator::run_for(picker().show(&ui), ms(2500)),
+        Some(Some(2))
+    );
+}
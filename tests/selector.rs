@@ -0,0 +1,721 @@
+//! Runs the whole selection flow on the emulator with `cargo host-test`.
+
+#![cfg(not(target_vendor = "vex"))]
+
+extern crate alloc;
+
+use std::{
+    cell::{Cell, RefCell},
+    fmt::Display,
+    rc::Rc,
+    time::Duration,
+};
+
+use autons_controller::{
+    dial::{Potentiometer, Switches},
+    display::{controller_str_width, CONTROLLER_WIDTH},
+    emulator::{self, Controller, WriteKind, TAP},
+    prelude::*,
+};
+
+struct Robot;
+
+impl Robot {
+    async fn left(&mut self) {}
+    async fn right(&mut self) {}
+}
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+/// Returns a controller which taps each of `buttons` in turn, one a second starting a second in.
+fn pressing(buttons: &[Button]) -> Rc<RefCell<Controller>> {
+    let mut controller = Controller::new();
+    for (i, &button) in buttons.iter().enumerate() {
+        controller.press(button, ms(1000 * (i as u64 + 1)), TAP);
+    }
+    Rc::new(RefCell::new(controller))
+}
+
+/// Returns the names of the routes that will run, in order.
+fn selected_names<C: Clone + Display + PartialEq + PartialOrd, const N: usize>(
+    selector: &ControllerSelect<Robot, C, N>,
+) -> Vec<String> {
+    selector
+        .selection()
+        .iter()
+        .map(|(route, _)| route.name.to_string())
+        .collect()
+}
+
+#[test]
+fn picks_a_route() {
+    let controller = Rc::new(RefCell::new(Controller::new()));
+    controller
+        .borrow_mut()
+        .press(Button::Right, ms(1000), TAP)
+        .press(Button::A, ms(2000), TAP)
+        // Move from "Cancel" to "Confirm"
+        .press(Button::Right, ms(3000), TAP)
+        .press(Button::A, ms(4000), TAP);
+    let selector = ControllerSelect::builder(
+        controller.clone(),
+        Rc::new(RefCell::new(true)),
+        [
+            route!("Match", "Left", Robot::left),
+            route!("Match", "Right", Robot::right),
+        ],
+    )
+    .skip_single_category(true)
+    .build();
+
+    emulator::advance(ms(5000));
+    let selection = selector.selection();
+    assert_eq!(selection.len(), 1);
+    assert_eq!(selection[0].0.name, "Right");
+    controller.borrow().screen.assert_rate_limited();
+}
+
+#[test]
+fn skips_the_category_list_and_confirmation() {
+    let controller = pressing(&[Button::Right, Button::A]);
+    let selector = ControllerSelect::builder(
+        controller.clone(),
+        Rc::new(RefCell::new(true)),
+        [
+            route!("Match", "Left", Robot::left),
+            route!("Match", "Right", Robot::right),
+        ],
+    )
+    .skip_single_category(true)
+    .skip_confirm(true)
+    .build();
+
+    // Opens on the only category's routes
+    emulator::advance(ms(500));
+    let frame = controller.borrow().screen.frame();
+    assert_eq!(
+        frame.lines().next().map(str::trim),
+        Some("- Match -"),
+        "{frame}"
+    );
+    // Picking a route selects it straight away
+    emulator::advance(ms(2000));
+    assert_eq!(selected_names(&selector), ["Right"]);
+    assert!(controller
+        .borrow()
+        .screen
+        .frame()
+        .contains("Route selected"));
+}
+
+#[test]
+fn picks_a_route_with_non_ascii_name() {
+    let controller = Rc::new(RefCell::new(Controller::new()));
+    controller
+        .borrow_mut()
+        .press(Button::Right, ms(1000), TAP)
+        .press(Button::Right, ms(1500), TAP)
+        .press(Button::A, ms(2000), TAP)
+        .press(Button::Right, ms(3000), TAP)
+        .press(Button::A, ms(4000), TAP);
+    // Long enough to scroll, with characters the controller's font doesn't have
+    let selector = ControllerSelect::builder(
+        controller.clone(),
+        Rc::new(RefCell::new(true)),
+        [
+            route!("Match", "Côté gauche → anneaux", Robot::left),
+            route!("Match", "Côté droit → pieux", Robot::right),
+            route!("Match", "Élimination 🚀", Robot::left),
+        ],
+    )
+    .skip_single_category(true)
+    .build();
+
+    emulator::advance(ms(5000));
+    let selection = selector.selection();
+    assert_eq!(selection.len(), 1);
+    assert_eq!(selection[0].0.name, "Élimination 🚀");
+    controller.borrow().screen.assert_rate_limited();
+}
+
+#[test]
+fn tells_apart_routes_with_the_same_name() {
+    let controller = Rc::new(RefCell::new(Controller::new()));
+    controller
+        .borrow_mut()
+        .press(Button::Right, ms(1000), TAP)
+        .press(Button::A, ms(2000), TAP)
+        .press(Button::Right, ms(3000), TAP)
+        .press(Button::A, ms(4000), TAP);
+    // One path follower registered for every path, where two paths ended up with one name
+    let followed = Rc::new(Cell::new(None));
+    let follow_path = |path: u32| {
+        let followed = followed.clone();
+        route!("Skills", "Path", |_, _| {
+            followed.set(Some(path));
+            async {}
+        })
+    };
+    let selector = ControllerSelect::builder(
+        controller.clone(),
+        Rc::new(RefCell::new(true)),
+        [follow_path(1), follow_path(2)],
+    )
+    .skip_single_category(true)
+    .build();
+
+    emulator::advance(ms(5000));
+    let selection = selector.selection();
+    assert_eq!(selection.len(), 1);
+    drop(
+        selection[0]
+            .0
+            .callback
+            .call(&mut Robot, selection[0].1.clone()),
+    );
+    assert_eq!(followed.get(), Some(2));
+}
+
+#[test]
+fn closure_routes_get_their_context() {
+    let controller = Rc::new(RefCell::new(Controller::new()));
+    controller
+        .borrow_mut()
+        .press(Button::A, ms(1000), TAP)
+        // Turn "Park" off
+        .press(Button::Right, ms(2000), TAP)
+        .press(Button::A, ms(3000), TAP)
+        .press(Button::Right, ms(4000), TAP)
+        .press(Button::A, ms(5000), TAP);
+    let parked = Rc::new(Cell::new(None));
+    let seen = parked.clone();
+    let selector = ControllerSelect::builder(
+        controller.clone(),
+        Rc::new(RefCell::new(true)),
+        [route!("Skills", "Path", |_, context| {
+            seen.set(context.bool("Park"));
+            context.fail();
+            async {}
+        })
+        .option(RouteOption::toggle("Park", true))],
+    )
+    .skip_single_category(true)
+    .build();
+
+    emulator::advance(ms(6000));
+    let selection = selector.selection();
+    assert_eq!(selection.len(), 1);
+    let (route, context) = &selection[0];
+    drop(route.callback.call(&mut Robot, context.clone()));
+    assert_eq!(parked.get(), Some(false));
+    assert!(context.has_failed());
+}
+
+#[test]
+fn dial_follows_potentiometer() {
+    let controller = Rc::new(RefCell::new(Controller::new()));
+    let angle = Rc::new(Cell::new(Some(10.0)));
+    let mut selector = ControllerSelect::builder(
+        controller.clone(),
+        Rc::new(RefCell::new(true)),
+        [
+            route!("Match", "Left", Robot::left),
+            route!("Match", "Right", Robot::right),
+        ],
+    )
+    .dial(Potentiometer::new(200.0, {
+        let angle = angle.clone();
+        move || angle.get()
+    }))
+    .build();
+    // Picked at startup, which the dial's starting position doesn't replace
+    selector.select(1);
+    let selected = || {
+        selector
+            .selection()
+            .first()
+            .map(|(route, _)| route.name.clone())
+    };
+
+    emulator::advance(ms(1000));
+    assert_eq!(selected().as_deref(), Some("Right"));
+    assert!(controller.borrow().screen.frame().contains("Pick category"));
+
+    angle.set(Some(150.0));
+    emulator::advance(ms(1000));
+    angle.set(Some(10.0));
+    emulator::advance(ms(1000));
+    assert_eq!(selected().as_deref(), Some("Left"));
+    assert!(controller.borrow().screen.frame().contains("Match / Left"));
+
+    // Just past the middle is within the hysteresis margin
+    angle.set(Some(110.0));
+    emulator::advance(ms(1000));
+    assert_eq!(selected().as_deref(), Some("Left"));
+
+    // A brief bump past the margin is debounced
+    angle.set(Some(150.0));
+    emulator::advance(ms(50));
+    angle.set(Some(10.0));
+    emulator::advance(ms(1000));
+    assert_eq!(selected().as_deref(), Some("Left"));
+
+    angle.set(Some(150.0));
+    emulator::advance(ms(1000));
+    assert_eq!(selected().as_deref(), Some("Right"));
+    assert!(controller.borrow().screen.frame().contains("Match / Right"));
+
+    // Unplugging the dial keeps the selection
+    angle.set(None);
+    emulator::advance(ms(1000));
+    assert_eq!(selected().as_deref(), Some("Right"));
+    controller.borrow().screen.assert_rate_limited();
+}
+
+#[test]
+fn dial_reads_switches_as_binary() {
+    let switches = [Rc::new(Cell::new(false)), Rc::new(Cell::new(false))];
+    let selector = ControllerSelect::builder(
+        Rc::new(RefCell::new(Controller::new())),
+        Rc::new(RefCell::new(true)),
+        [
+            route!("Match", "Left", Robot::left),
+            route!("Match", "Right", Robot::right),
+            route!("Skills", "Skills", Robot::left),
+        ],
+    )
+    .dial(Switches::new(switches.iter().map(|switch| {
+        let switch = switch.clone();
+        Box::new(move || Some(switch.get())) as Box<dyn FnMut() -> Option<bool>>
+    })))
+    .dial_debounce(ms(0))
+    .build();
+    let selected = || {
+        selector
+            .selection()
+            .first()
+            .map(|(route, _)| route.name.clone())
+    };
+
+    // Nothing is selected until the switches change
+    emulator::advance(ms(100));
+    assert_eq!(selected(), None);
+    switches[1].set(true);
+    emulator::advance(ms(100));
+    assert_eq!(selected().as_deref(), Some("Skills"));
+    // Three is past the last route
+    switches[0].set(true);
+    emulator::advance(ms(100));
+    assert_eq!(selected().as_deref(), Some("Skills"));
+    switches[1].set(false);
+    emulator::advance(ms(100));
+    assert_eq!(selected().as_deref(), Some("Right"));
+    switches[0].set(false);
+    emulator::advance(ms(100));
+    assert_eq!(selected().as_deref(), Some("Left"));
+}
+
+#[test]
+fn flat_list_jumps_between_categories() {
+    let controller = pressing(&[
+        Button::R1,
+        Button::L1,
+        // Wraps around to the last category
+        Button::L1,
+        Button::A,
+        Button::Right,
+        Button::A,
+    ]);
+    let selector = ControllerSelect::builder(
+        controller.clone(),
+        Rc::new(RefCell::new(true)),
+        [
+            route!("Skills", "Skills", Robot::left),
+            route!("Match", "Left", Robot::left),
+            route!("Match", "Right", Robot::right),
+            route!("Match", "Middle", Robot::right),
+        ],
+    )
+    .flat_list(true)
+    .position_indicator(true)
+    .build();
+
+    emulator::advance(ms(7000));
+    let screen = &controller.borrow().screen;
+    // Grouped in category order, whatever order the routes were declared in
+    assert!(screen.frame_at(ms(1500)).contains("4/4"));
+    assert!(screen.frame_at(ms(2500)).contains("1/4"));
+    assert!(screen.frame_at(ms(3500)).contains("4/4"));
+    assert_eq!(selected_names(&selector), ["Skills"]);
+}
+
+#[test]
+fn counts_down_the_start_delay() {
+    let controller = pressing(&[
+        Button::A,
+        // Three half-second steps
+        Button::Right,
+        Button::Right,
+        Button::Right,
+        Button::A,
+    ]);
+    let ran = Rc::new(Cell::new(None));
+    let ran_at = ran.clone();
+    let selector = ControllerSelect::builder(
+        controller.clone(),
+        Rc::new(RefCell::new(true)),
+        [route!("Match", "Left", move |_, _| {
+            ran_at.set(Some(emulator::Instant::now().since_start()));
+            async {}
+        })],
+    )
+    .skip_single_category(true)
+    .skip_confirm(true)
+    .start_delay(ms(2000))
+    .build();
+
+    emulator::advance(ms(6000));
+    assert_eq!(selected_names(&selector), ["Left"]);
+    assert_eq!(selector.start_delay(), ms(1500));
+    assert!(controller.borrow().screen.frame().contains("+1.5s"));
+
+    emulator::set_competition_connected(true);
+    emulator::advance(ms(1500));
+    let start = emulator::Instant::now().since_start();
+    emulator::run_for(selector.run_selection(&mut Robot), ms(3000)).unwrap();
+    assert!(ran.get().is_some_and(|at| at >= start + ms(1500)));
+    emulator::advance(ms(1000));
+    let screen = &controller.borrow().screen;
+    assert!(screen.frame_at(start + ms(500)).contains("Starting in 1."));
+    assert!(screen.frame().contains("Go!"));
+}
+
+#[test]
+fn start_delay_stops_at_the_last_whole_step() {
+    let controller = pressing(&[
+        Button::A,
+        Button::Right,
+        Button::Right,
+        Button::Right,
+        // Past 1.5s, which is as far as whole steps go
+        Button::Right,
+        Button::A,
+    ]);
+    let selector = ControllerSelect::builder(
+        controller.clone(),
+        Rc::new(RefCell::new(true)),
+        [route!("Match", "Left", Robot::left)],
+    )
+    .skip_single_category(true)
+    .skip_confirm(true)
+    .start_delay(ms(1700))
+    .build();
+
+    emulator::advance(ms(7000));
+    assert_eq!(selected_names(&selector), ["Left"]);
+    assert_eq!(selector.start_delay(), ms(1500));
+}
+
+#[test]
+fn retries_failed_checks() {
+    let controller = pressing(&[Button::A, Button::Right, Button::A]);
+    let attempts = Rc::new(Cell::new(0));
+    let counted = attempts.clone();
+    let selector = ControllerSelect::builder(
+        controller.clone(),
+        Rc::new(RefCell::new(true)),
+        [route!("Match", "Left", Robot::left)],
+    )
+    .skip_single_category(true)
+    .skip_confirm(true)
+    // Passes once the driver has fixed it
+    .check(Check::new("Arm homed", move || {
+        counted.set(counted.get() + 1);
+        let passed = counted.get() > 1;
+        async move { passed }
+    }))
+    .build();
+
+    emulator::advance(ms(4000));
+    assert!(controller
+        .borrow()
+        .screen
+        .frame_at(ms(2500))
+        .contains("1 check failed"));
+    assert_eq!(attempts.get(), 2);
+    assert_eq!(selected_names(&selector), ["Left"]);
+}
+
+#[test]
+fn overrides_failed_checks() {
+    // Back around to the last entry
+    let controller = pressing(&[Button::A, Button::Left, Button::A]);
+    let selector = ControllerSelect::builder(
+        controller.clone(),
+        Rc::new(RefCell::new(true)),
+        [route!("Match", "Left", Robot::left)],
+    )
+    .skip_single_category(true)
+    .skip_confirm(true)
+    .check(Check::new("Arm homed", || async { false }))
+    .check(Check::new("Tray down", || async { false }))
+    .override_label("Run anyway")
+    .build();
+
+    emulator::advance(ms(2500));
+    let frame = controller.borrow().screen.frame();
+    assert!(frame.contains("2 checks failed"), "{frame}");
+    assert!(frame.contains("Run anyway"), "{frame}");
+    assert!(selector.selection().is_empty());
+    emulator::advance(ms(1500));
+    assert_eq!(selected_names(&selector), ["Left"]);
+}
+
+#[test]
+fn playlist_reorders_and_stops_at_a_failure() {
+    let controller = pressing(&[
+        // Add "Right"
+        Button::A,
+        Button::Right,
+        Button::A,
+        // Add "Left" after it
+        Button::Right,
+        Button::A,
+        Button::A,
+        // Move "Left" up
+        Button::A,
+        Button::A,
+        // Back around to "Confirm"
+        Button::Left,
+        Button::A,
+    ]);
+    let ran = Rc::new(RefCell::new(Vec::new()));
+    let recorded = |name: &'static str, fails: bool| {
+        let ran = ran.clone();
+        route!("Match", name, move |_, context| {
+            ran.borrow_mut().push(name);
+            if fails {
+                context.fail();
+            }
+            async {}
+        })
+    };
+    let selector = ControllerSelect::builder(
+        controller.clone(),
+        Rc::new(RefCell::new(true)),
+        [recorded("Left", true), recorded("Right", false)],
+    )
+    .skip_single_category(true)
+    .playlist(true)
+    .build();
+
+    emulator::advance(ms(11000));
+    assert_eq!(selected_names(&selector), ["Left", "Right"]);
+
+    // Autonomous starts once the field is connected, which closes the selector
+    emulator::set_competition_connected(true);
+    emulator::advance(ms(1500));
+    emulator::run_for(selector.run_selection(&mut Robot), ms(1000)).unwrap();
+    emulator::advance(ms(1000));
+    assert_eq!(*ran.borrow(), ["Left"]);
+    assert!(controller
+        .borrow()
+        .screen
+        .frame()
+        .contains("Stopped at Left"));
+}
+
+#[test]
+fn back_returns_to_where_the_driver_left_off() {
+    let controller = pressing(&[
+        Button::Right,
+        Button::A,
+        Button::B,
+        // From "Skills" back to "Match", which only works if the category list kept its place
+        Button::Left,
+        Button::A,
+        Button::Right,
+        Button::B,
+        // "Match" opens on "Right" again
+        Button::A,
+        Button::A,
+        Button::Right,
+        Button::A,
+    ]);
+    let selector = ControllerSelect::builder(
+        controller.clone(),
+        Rc::new(RefCell::new(true)),
+        [
+            route!("Match", "Left", Robot::left),
+            route!("Match", "Right", Robot::right),
+            route!("Skills", "Skills", Robot::left),
+        ],
+    )
+    .build();
+
+    emulator::advance(ms(12000));
+    assert_eq!(selected_names(&selector), ["Right"]);
+}
+
+/// Returns a selector which asks whether to park only after "Right" is picked.
+fn asks_to_park_after_right(
+    controller: Rc<RefCell<Controller>>,
+) -> ControllerSelect<Robot, &'static str, 2> {
+    ControllerSelect::builder(
+        controller,
+        Rc::new(RefCell::new(true)),
+        [
+            route!("Match", "Left", Robot::left),
+            route!("Match", "Right", Robot::right),
+        ],
+    )
+    .skip_single_category(true)
+    .skip_confirm(true)
+    .screen_if(
+        FlowPoint::AfterRoute,
+        |flow| flow.route().is_some_and(|route| route.name == "Right"),
+        || Ask::new(RouteOption::toggle("Park", true)),
+    )
+    .build()
+}
+
+#[test]
+fn screen_if_asks_when_its_condition_holds() {
+    // Turn "Park" off
+    let controller = pressing(&[Button::Right, Button::A, Button::Right, Button::A]);
+    let selector = asks_to_park_after_right(controller.clone());
+
+    emulator::advance(ms(5000));
+    assert!(controller
+        .borrow()
+        .screen
+        .frame_at(ms(2900))
+        .contains("Park"));
+    let selection = selector.selection();
+    assert_eq!(selection[0].0.name, "Right");
+    assert_eq!(selection[0].1.bool("Park"), Some(false));
+}
+
+#[test]
+fn screen_if_skips_when_its_condition_fails() {
+    let selector = asks_to_park_after_right(pressing(&[Button::A]));
+
+    emulator::advance(ms(2000));
+    let selection = selector.selection();
+    assert_eq!(selection[0].0.name, "Left");
+    assert_eq!(selection[0].1.bool("Park"), None);
+}
+
+#[test]
+fn breadcrumbs_keep_the_current_step_in_view() {
+    let controller = pressing(&[Button::A]);
+    let _selector = ControllerSelect::builder(
+        controller.clone(),
+        Rc::new(RefCell::new(true)),
+        [route!("Match", "Left", Robot::left).option(RouteOption::toggle("Preload", true))],
+    )
+    .skip_single_category(true)
+    .breadcrumbs(true)
+    .build();
+
+    emulator::advance(ms(2000));
+    let frame = controller.borrow().screen.frame();
+    // "Match > Left > Preload" is too wide, so the front of the trail gives way
+    assert_eq!(
+        frame.lines().next().map(str::trim),
+        Some(".. > Left > Preload"),
+        "{frame}"
+    );
+}
+
+/// Returns a selector which shows the battery levels once "Left" is picked.
+fn battery_status_selector(
+    controller: Rc<RefCell<Controller>>,
+) -> ControllerSelect<Robot, &'static str, 1> {
+    ControllerSelect::builder(
+        controller,
+        Rc::new(RefCell::new(true)),
+        [route!("Match", "Left", Robot::left)],
+    )
+    .skip_single_category(true)
+    .skip_confirm(true)
+    .battery_status(0.3)
+    .build()
+}
+
+#[test]
+fn warns_about_a_low_battery() {
+    emulator::set_brain_battery(0.2);
+    let controller = pressing(&[Button::A]);
+    let _selector = battery_status_selector(controller.clone());
+
+    emulator::advance(ms(5000));
+    let screen = &controller.borrow().screen;
+    let frames = (20..50)
+        .map(|tenths| screen.frame_at(ms(tenths * 100)))
+        .collect::<Vec<_>>();
+    // The warning blinks with the levels
+    assert!(frames.iter().any(|frame| frame.contains("! LOW BATTERY !")));
+    assert!(frames
+        .iter()
+        .any(|frame| frame.contains("Brain 20% Ctrl 100%")));
+    let rumbles = screen
+        .writes()
+        .iter()
+        .filter(|write| matches!(write.kind, WriteKind::Rumble(_)))
+        .count();
+    assert_eq!(rumbles, 1);
+}
+
+#[test]
+fn shows_healthy_battery_levels() {
+    emulator::set_brain_battery(0.9);
+    let controller = pressing(&[Button::A]);
+    controller.borrow_mut().set_battery(0.55);
+    let _selector = battery_status_selector(controller.clone());
+
+    emulator::advance(ms(5000));
+    let screen = &controller.borrow().screen;
+    assert!(screen.frame().contains("Brain 90% Ctrl 55%"));
+    assert!(!screen.writes().iter().any(
+        |write| matches!(write.kind, WriteKind::Rumble(_)) || write.to_string().contains("LOW")
+    ));
+}
+
+#[test]
+fn done_screen_fits_a_long_description() {
+    let controller = pressing(&[
+        Button::A,
+        // "Goal" preload
+        Button::Right,
+        Button::A,
+        // Half a second of delay
+        Button::Right,
+        Button::A,
+    ]);
+    let selector = ControllerSelect::builder(
+        controller.clone(),
+        Rc::new(RefCell::new(true)),
+        [
+            route!("Match", "Left side rush", Robot::left).option(RouteOption::choice(
+                "Preload",
+                ["Ring", "Goal"],
+                0,
+            )),
+        ],
+    )
+    .skip_single_category(true)
+    .skip_confirm(true)
+    .start_delay(ms(2000))
+    .build();
+
+    emulator::advance(ms(6000));
+    assert_eq!(selected_names(&selector), ["Left side rush"]);
+    let frame = controller.borrow().screen.frame();
+    let description = frame.lines().nth(1).unwrap().trim();
+    assert!(description.starts_with("Match / Left side"), "{frame}");
+    assert!(description.ends_with(".."), "{frame}");
+    assert!(controller_str_width(description) <= CONTROLLER_WIDTH);
+}
@@ -0,0 +1,370 @@
+//! Snapshot tests for the widgets, run on the emulator with `cargo host-test`.
+//!
+//! Each snapshot is the screen rendered as text and lives in `tests/snapshots`. Run with
+//! `UPDATE_SNAPSHOTS=1` to write new snapshots after an intended change to a layout.
+
+#![cfg(not(target_vendor = "vex"))]
+
+use std::{cell::RefCell, fs, path::PathBuf, rc::Rc, time::Duration};
+
+use autons_controller::{
+    display::{
+        center_string, controller_str_width, fit_breadcrumbs, header_string, truncate_string,
+        underline_string, ControllerRenderer, CONTROLLER_WIDTH,
+    },
+    emulator::{self, Controller, WriteKind, TAP},
+    view::{Renderer, View},
+    widgets::{ControllerUi, Dialog, Picker, Spinner, Toggle},
+    Button,
+};
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+/// Compares `frame` against the snapshot called `name`, writing it instead if it is missing or
+/// `UPDATE_SNAPSHOTS` is set.
+#[track_caller]
+fn assert_snapshot(name: &str, frame: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{name}.txt"));
+    // A border keeps leading and trailing spaces visible in the snapshot file
+    let frame = frame
+        .split('\n')
+        .map(|line| format!("|{line}|\n"))
+        .collect::<String>();
+    match fs::read_to_string(&path) {
+        Ok(snapshot) if std::env::var_os("UPDATE_SNAPSHOTS").is_none() => {
+            assert_eq!(frame, snapshot, "snapshot `{name}` doesn't match");
+        }
+        _ => fs::write(&path, frame).unwrap(),
+    }
+}
+
+fn setup() -> (Rc<RefCell<Controller>>, ControllerUi) {
+    let controller = Rc::new(RefCell::new(Controller::new()));
+    let ui = ControllerUi::new(controller.clone(), Rc::new(RefCell::new(true)));
+    (controller, ui)
+}
+
+fn options(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[test]
+fn picker_first_option() {
+    let (controller, ui) = setup();
+    let picker = Picker::new("Route", options(&["Left", "Right", "Skills"]));
+    assert_eq!(emulator::run_for(picker.show(&ui), ms(1000)), None);
+
+    let controller = controller.borrow();
+    assert_snapshot("picker_first_option", &controller.screen.frame());
+    controller.screen.assert_rate_limited();
+}
+
+#[test]
+fn picker_moves_and_confirms() {
+    let (controller, ui) = setup();
+    controller
+        .borrow_mut()
+        .press(Button::Right, ms(500), TAP)
+        .press(Button::Right, ms(800), TAP)
+        .press(Button::A, ms(2000), TAP);
+    let picker = Picker::new("Route", options(&["Left", "Right", "Skills"]));
+    assert_eq!(emulator::run_for(picker.show(&ui), ms(3000)), Some(Some(2)));
+
+    let controller = controller.borrow();
+    assert_snapshot("picker_moved", &controller.screen.frame_at(ms(1900)));
+    // The picker clears up after itself
+    assert_eq!(controller.screen.frame(), "\n\n");
+    controller.screen.assert_rate_limited();
+}
+
+#[test]
+fn picker_backs_out() {
+    let (controller, ui) = setup();
+    controller.borrow_mut().press(Button::B, ms(500), TAP);
+    let picker = Picker::new("Route", options(&["Left", "Right"]));
+    assert_eq!(emulator::run_for(picker.show(&ui), ms(1000)), Some(None));
+    controller.borrow().screen.assert_rate_limited();
+}
+
+#[test]
+fn picker_scrolls_long_lists() {
+    let (controller, ui) = setup();
+    controller.borrow_mut().press(Button::Left, ms(1000), TAP);
+    let picker = Picker::new(
+        "Route",
+        options(&[
+            "Solo AWP",
+            "Ring Rush",
+            "Goal Rush",
+            "Elims",
+            "Skills",
+            "Testing",
+        ]),
+    )
+    .selected(3)
+    .position_indicator(true);
+    assert_eq!(emulator::run_for(picker.show(&ui), ms(2000)), None);
+
+    let controller = controller.borrow();
+    assert_snapshot("picker_scrolled", &controller.screen.frame_at(ms(900)));
+    assert_snapshot("picker_scrolled_left", &controller.screen.frame());
+    controller.screen.assert_rate_limited();
+}
+
+#[test]
+fn dialog_draw() {
+    let (controller, ui) = setup();
+    let dialog = Dialog::new("Battery low", "Swap it before").supporting("the match");
+    emulator::run_for(dialog.draw(&ui), ms(1000)).unwrap();
+
+    let controller = controller.borrow();
+    assert_snapshot("dialog_draw", &controller.screen.frame());
+    controller.screen.assert_rate_limited();
+}
+
+#[test]
+fn dialog_confirms() {
+    let (controller, ui) = setup();
+    controller.borrow_mut().press(Button::A, ms(1200), TAP);
+    let dialog = Dialog::new("IMU drift", "Recalibrate?");
+    assert_eq!(
+        emulator::run_for(dialog.show(&ui), ms(2000)),
+        Some(Some(()))
+    );
+
+    let controller = controller.borrow();
+    assert_snapshot("dialog_shown", &controller.screen.frame_at(ms(1100)));
+    controller.screen.assert_rate_limited();
+}
+
+#[test]
+fn spinner_stops_at_its_bounds() {
+    let (controller, ui) = setup();
+    controller
+        .borrow_mut()
+        // Stops at the top instead of wrapping around to the bottom
+        .press(Button::Right, ms(500), TAP)
+        .press(Button::Right, ms(800), TAP)
+        // Ten steps down goes no lower than the bottom
+        .press(Button::L1, ms(1100), TAP)
+        .press(Button::Left, ms(1400), TAP)
+        .press(Button::Right, ms(1700), TAP)
+        .press(Button::A, ms(2000), TAP);
+    let spinner = Spinner::new("Delay", 0.0, 1.5, 0.5)
+        .value(1.0)
+        .decimals(1)
+        .suffix("s");
+    assert_eq!(
+        emulator::run_for(spinner.show(&ui), ms(3000)),
+        Some(Some(0.5))
+    );
+
+    let controller = controller.borrow();
+    // Only the arrow that still changes the value is shown
+    let value_at = |at| {
+        controller
+            .screen
+            .frame_at(at)
+            .lines()
+            .nth(1)
+            .map(|line| line.trim().to_string())
+    };
+    assert_eq!(value_at(ms(1050)).as_deref(), Some("< 1.5s"));
+    assert_eq!(value_at(ms(1650)).as_deref(), Some("0.0s >"));
+    controller.screen.assert_rate_limited();
+}
+
+#[test]
+fn spinner_clamps_its_starting_value() {
+    let (controller, ui) = setup();
+    controller.borrow_mut().press(Button::A, ms(500), TAP);
+    let spinner = Spinner::new("Rings", 0.0, 4.0, 1.0).value(9.0);
+    assert_eq!(
+        emulator::run_for(spinner.show(&ui), ms(1000)),
+        Some(Some(4.0))
+    );
+}
+
+#[test]
+fn toggle_flips_both_ways() {
+    let (controller, ui) = setup();
+    controller
+        .borrow_mut()
+        .press(Button::Right, ms(500), TAP)
+        .press(Button::Left, ms(1000), TAP)
+        .press(Button::Left, ms(1500), TAP)
+        .press(Button::A, ms(2000), TAP);
+    let toggle = Toggle::new("Park", false).labels("No", "Yes");
+    assert_eq!(
+        emulator::run_for(toggle.show(&ui), ms(3000)),
+        Some(Some(true))
+    );
+
+    let controller = controller.borrow();
+    assert!(controller.screen.frame_at(ms(450)).contains("< No >"));
+    assert!(controller.screen.frame_at(ms(950)).contains("< Yes >"));
+    assert!(controller.screen.frame_at(ms(1450)).contains("< No >"));
+    controller.screen.assert_rate_limited();
+}
+
+#[test]
+fn controller_draws_the_picker_view() {
+    let (controller, ui) = setup();
+    controller.borrow_mut().press(Button::Right, ms(500), TAP);
+    let picker = Picker::new("Route", options(&["Left", "Right", "Skills"]));
+    assert_eq!(emulator::run_for(picker.show(&ui), ms(1500)), None);
+
+    let mut renderer = ControllerRenderer::new();
+    renderer.render(&View::List {
+        title: "Route".into(),
+        status: None,
+        options: options(&["Left", "Right", "Skills"]),
+        selected: 1,
+    });
+    // The emulator doesn't keep trailing spaces
+    let lines = renderer.lines().each_ref().map(|line| line.trim_end());
+    assert_eq!(controller.borrow().screen.frame(), lines.join("\n"));
+}
+
+#[test]
+fn controller_renderer_lays_out_values_and_messages() {
+    let mut renderer = ControllerRenderer::new();
+    renderer.render(&View::Value {
+        title: "Delay".into(),
+        value: "1.5s".into(),
+        can_decrease: false,
+        can_increase: true,
+    });
+    assert_eq!(
+        renderer.lines(),
+        &[
+            center_string("Delay"),
+            center_string("  1.5s >"),
+            String::new()
+        ]
+    );
+
+    // A message without a title leaves the first line alone
+    renderer.render(&View::message("Go!"));
+    assert_eq!(
+        renderer.lines(),
+        &[String::new(), center_string("Go!"), String::new()]
+    );
+
+    // Lines too wide for the screen are shortened
+    renderer.render(&View::Dialog {
+        title: "- Route selected -".into(),
+        description: "Match / Left side rush +1.5s Preload=Goal".into(),
+        supporting: Some("Good luck!".into()),
+    });
+    let description = renderer.lines()[1].trim();
+    assert!(description.starts_with("Match / Left"), "{description}");
+    assert!(description.ends_with(".."), "{description}");
+    assert!(controller_str_width(description) <= CONTROLLER_WIDTH);
+
+    renderer.render(&View::Blank);
+    assert!(renderer.lines().iter().all(String::is_empty));
+}
+
+#[test]
+fn center_and_underline() {
+    let (controller, ui) = setup();
+    let text = "Left   Right   Skills";
+    let draw = async {
+        ui.set_text(center_string("Centered"), 1, 1).await;
+        ui.set_text(text, 2, 1).await;
+        ui.set_text(underline_string(text, '-', 7..12), 3, 1).await;
+    };
+    emulator::run_for(draw, ms(1000)).unwrap();
+
+    let controller = controller.borrow();
+    assert_snapshot("center_and_underline", &controller.screen.frame());
+    controller.screen.assert_rate_limited();
+}
+
+#[test]
+fn truncates_to_the_width() {
+    assert_eq!(truncate_string("Skills", CONTROLLER_WIDTH), "Skills");
+    assert_eq!(truncate_string("", 0), "");
+    let cut = truncate_string("Solo AWP with the alliance stake", 60);
+    assert!(cut.starts_with("Solo") && cut.ends_with(".."), "{cut}");
+    assert!(controller_str_width(&cut) <= 60);
+    // Too narrow for even the ellipsis
+    assert_eq!(truncate_string("Skills", 2), "");
+}
+
+#[test]
+fn breadcrumbs_give_way_from_the_front() {
+    assert_eq!(
+        fit_breadcrumbs("Match > Left", CONTROLLER_WIDTH),
+        "Match > Left"
+    );
+    assert_eq!(
+        fit_breadcrumbs("Match > Left > Preload", CONTROLLER_WIDTH),
+        ".. > Left > Preload"
+    );
+    assert_eq!(
+        fit_breadcrumbs("Match > Left side rush > Preload", CONTROLLER_WIDTH),
+        ".. > Preload"
+    );
+    // The last crumb is cut short when it doesn't fit on its own
+    let last = fit_breadcrumbs("Match > Preload two rings and a goal", CONTROLLER_WIDTH);
+    assert!(
+        last.starts_with("Preload") && last.ends_with(".."),
+        "{last}"
+    );
+    assert!(controller_str_width(&last) <= CONTROLLER_WIDTH);
+    // Without a separator, the title is only truncated
+    let title = "Solo AWP with the alliance stake";
+    assert_eq!(
+        fit_breadcrumbs(title, CONTROLLER_WIDTH),
+        truncate_string(title, CONTROLLER_WIDTH)
+    );
+}
+
+#[test]
+fn header_fits_the_title_next_to_the_status() {
+    assert_eq!(header_string("Route", None).trim(), "Route");
+    let header = header_string("Route", Some("3/7"));
+    assert!(
+        header.starts_with("Route ") && header.ends_with(" 3/7"),
+        "{header}"
+    );
+    assert!(controller_str_width(&header) <= CONTROLLER_WIDTH);
+    // The status takes room the title would have fit in on its own
+    let header = header_string("Match > Left > Rings", Some("12/12"));
+    assert!(header.starts_with(".. > Left > Rings"), "{header}");
+    assert!(header.ends_with(" 12/12"), "{header}");
+    assert!(controller_str_width(&header) <= CONTROLLER_WIDTH);
+    // A status wider than the line leaves no room for the title at all
+    let status = "Status wider than the whole screen";
+    assert_eq!(header_string("Route", Some(status)).trim(), status);
+}
+
+#[test]
+fn flags_writes_too_close_together() {
+    let (controller, ui) = setup();
+    let draw = async {
+        ui.set_text("Spaced out", 1, 1).await;
+        // Skips the UI's rate limiting
+        emulator::sleep(ms(50)).await;
+        _ = controller
+            .borrow_mut()
+            .screen
+            .try_set_text("Too soon", 2, 1);
+    };
+    emulator::run_for(draw, ms(1000)).unwrap();
+
+    let controller = controller.borrow();
+    let violations = controller.screen.rate_violations();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].1.at - violations[0].0.at, ms(50));
+    assert!(matches!(
+        &violations[0].1.kind,
+        WriteKind::Text { line: 2, .. }
+    ));
+}